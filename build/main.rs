@@ -26,7 +26,8 @@ impl DataType {
         Self {
             var_name: format_ident!("{}", var_name),
             func_name,
-            type_name: typ.split("::").last().unwrap().to_string(),
+            //the innermost type, so shared strings are named String
+            type_name: typ.trim_end_matches('>').split("::").last().unwrap().to_string(),
             typ: syn::parse_str(typ).unwrap(),
         }
     }
@@ -59,6 +60,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         ("USize", "usize", "usize"),
         ("ISize", "isize", "isize"),
         ("Float", "float", "::sched::Float"),
+        ("String", "string", "::std::sync::Arc<::std::string::String>"),
 
         //complex types
         ("ClockData", "clock_data", "::sched::binding::bpm::ClockData"),
@@ -82,6 +84,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let mut access_names = Vec::new();
 
         let mut param_typed_getters = Vec::new();
+        let mut value_json = Vec::new();
+        let mut set_json = Vec::new();
         
         for a in access.iter() {
             let ename = a.enum_name.clone();
@@ -132,6 +136,45 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let t = v.typ.clone();
             let ename = v.var_name.clone();
 
+            value_json.push(quote! {
+                Self::Get(ParamDataGet::#ename(d)) => serde_json::to_value(d.get()).ok()
+            });
+            value_json.push(quote! {
+                Self::GetSet(ParamDataGetSet::#ename(d)) => serde_json::to_value(d.get()).ok()
+            });
+            set_json.push(quote! {
+                Self::Set(ParamDataSet::#ename(d)) => serde_json::from_value::<#t>(value).map(|v| d.set(v)).map_err(|_| ())
+            });
+            set_json.push(quote! {
+                Self::GetSet(ParamDataGetSet::#ename(d)) => serde_json::from_value::<#t>(value).map(|v| d.set(v)).map_err(|_| ())
+            });
+
+            //key values are arrays of their values
+            value_json.push(quote! {
+                Self::KeyValueGet(ParamDataKeyValueGet::#ename(d)) => serde_json::to_value(
+                    (0..d.len().unwrap_or(0)).filter_map(|i| d.get_at(i)).collect::<Vec<#t>>()
+                ).ok()
+            });
+            value_json.push(quote! {
+                Self::KeyValueGetSet(ParamDataKeyValueGetSet::#ename(d)) => serde_json::to_value(
+                    (0..d.len().unwrap_or(0)).filter_map(|i| d.get_at(i)).collect::<Vec<#t>>()
+                ).ok()
+            });
+            set_json.push(quote! {
+                Self::KeyValueSet(ParamDataKeyValueSet::#ename(d)) => serde_json::from_value::<Vec<#t>>(value).map(|v| {
+                    for (i, v) in v.into_iter().enumerate() {
+                        let _ = d.set_at(i, v);
+                    }
+                }).map_err(|_| ())
+            });
+            set_json.push(quote! {
+                Self::KeyValueGetSet(ParamDataKeyValueGetSet::#ename(d)) => serde_json::from_value::<Vec<#t>>(value).map(|v| {
+                    for (i, v) in v.into_iter().enumerate() {
+                        let _ = d.set_at(i, v);
+                    }
+                }).map_err(|_| ())
+            });
+
             param_typed_getters.push(quote! {
                 impl std::convert::TryInto<::std::sync::Arc<dyn ParamBindingGet<#t>>> for &Param {
                     type Error = ();
@@ -190,6 +233,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             #(#access_names),*
                         }
                     }
+
+                    /// Get the current value as json, if there is get access.
+                    pub fn value_json(&self) -> Option<serde_json::Value> {
+                        match self {
+                            #(#value_json,)*
+                            _ => None
+                        }
+                    }
+
+                    /// Set the value from json, if there is set access and the json has the right type.
+                    pub fn set_json(&self, value: serde_json::Value) -> Result<(), ()> {
+                        match self {
+                            #(#set_json,)*
+                            _ => Err(())
+                        }
+                    }
                 }
                 #(#froms)*
 
@@ -206,11 +265,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         let mut pset = Vec::new();
         let mut param_data_type_name = Vec::new();
         let mut unbind = Vec::new();
+        let mut get_unbind = Vec::new();
 
         for v in variants.iter() {
             let i = v.var_name.clone();
             let t = v.typ.clone();
             let tname = v.type_name.clone();
+            let kv = format_ident!("KeyValue{}", i);
+            let kvname = format!("KeyValue<{}>", tname);
+
+            param_data_type_name.push(quote! {
+                    Self::Get{ get : ParamGet::#kv(..), .. } => &#kvname
+                });
+            pget.push(quote! {
+                #kv(::std::sync::Arc<array::BindingSwapKeyValueGet<#t>>)
+            });
+            get_unbind.push(quote! {
+                Self::#kv(b) => {
+                    b.unbind();
+                }
+            });
 
             param_data_type_name.push(quote! {
                     Self::Get{ get : ParamGet::#i(..), .. } | Self::Set { set: ParamSet::#i(..), .. } => &#tname
@@ -226,6 +300,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     b.unbind();
                 }
             });
+            get_unbind.push(quote! {
+                Self::#i(b) => {
+                    b.unbind();
+                }
+            });
         }
         params_file.write_all(
             quote! {
@@ -241,7 +320,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     //TODO transform and return output?
                     pub fn unbind(&self) {
                         match self {
-                            #(#unbind),*
+                            #(#get_unbind),*
                         }
                     }
                 }
@@ -268,8 +347,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let i = v.var_name.clone();
             let fname = v.func_name.clone();
             let t = v.typ.clone();
+            let kv = format_ident!("KeyValue{}", i);
 
             try_bind_variants.push(quote! {
+                ParamAccess::Get { get: ParamGet::#kv(p), binding: b } => {
+                    if let Ok(g) = binding.as_ref().try_into() {
+                        let mut l = b.lock();
+                        p.bind(g);
+                        l.replace(binding);
+                        Ok(())
+                    } else {
+                        Err(BindingError::NoGet)
+                    }
+                }
                 ParamAccess::Get { get: ParamGet::#i(p), binding: b } => {
                     if let Ok(g) = binding.as_ref().try_into() {
                         let mut l = b.lock();
@@ -372,6 +462,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         pub osc_type: &'static str,
         pub get_func: TokenStream,
         pub set_func: TokenStream,
+        //convert a value `v` to the osc_type, for array elements
+        pub to_raw: TokenStream,
+        //convert an osc_type `v` to a value, for array elements
+        pub from_raw: TokenStream,
+        //convert an `o: &OscType` to an Option of the value, for array elements
+        pub from_osc: TokenStream,
         pub clip: Option<TokenStream>,
         pub range: Option<TokenStream>,
     }
@@ -384,6 +480,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 osc_type: &"",
                 get_func: quote! { unimplemented!(); },
                 set_func: quote! { unimplemented!(); },
+                to_raw: quote! { unimplemented!() },
+                from_raw: quote! { unimplemented!() },
+                from_osc: quote! { unimplemented!() },
                 clip: None,
                 range: None,
             }
@@ -402,6 +501,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 set_func: quote! {
                     s.upgrade().map(|s| s.set(v));
                 },
+                to_raw: quote! {
+                    v
+                },
+                from_raw: quote! {
+                    v
+                },
+                from_osc: quote! {
+                    match o {
+                        OscType::Bool(v) => Some(*v),
+                        _ => None
+                    }
+                },
                 ..Default::default()
             },
             SimpBindingValue {
@@ -414,6 +525,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 set_func: quote! {
                     s.upgrade().map(|s| s.set(num::clamp(v, 0, 255) as u8));
                 },
+                to_raw: quote! {
+                    v as i32
+                },
+                from_raw: quote! {
+                    num::clamp(v, 0, 255) as u8
+                },
+                from_osc: quote! {
+                    match o {
+                        OscType::Int(v) => Some(num::clamp(*v, 0, 255) as u8),
+                        OscType::Long(v) => Some(num::clamp(*v, 0, 255) as u8),
+                        _ => None
+                    }
+                },
                 clip: Some(quote! {
                     ClipMode::Both
                 }),
@@ -432,6 +556,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 set_func: quote! {
                     s.upgrade().map(|s| s.set(std::cmp::max(v, 0i64) as usize));
                 },
+                to_raw: quote! {
+                    v as i64
+                },
+                from_raw: quote! {
+                    std::cmp::max(v, 0i64) as usize
+                },
+                from_osc: quote! {
+                    match o {
+                        OscType::Long(v) => Some(std::cmp::max(*v, 0i64) as usize),
+                        OscType::Int(v) => Some(std::cmp::max(*v, 0i32) as usize),
+                        _ => None
+                    }
+                },
                 clip: Some(quote! {
                     ClipMode::Low
                 }),
@@ -450,6 +587,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 set_func: quote! {
                     s.upgrade().map(|s| s.set(v as isize));
                 },
+                to_raw: quote! {
+                    v as i64
+                },
+                from_raw: quote! {
+                    v as isize
+                },
+                from_osc: quote! {
+                    match o {
+                        OscType::Long(v) => Some(*v as isize),
+                        OscType::Int(v) => Some(*v as isize),
+                        _ => None
+                    }
+                },
                 ..Default::default()
             },
             SimpBindingValue {
@@ -462,17 +612,58 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 set_func: quote! {
                     s.upgrade().map(|s| s.set(v));
                 },
+                to_raw: quote! {
+                    v
+                },
+                from_raw: quote! {
+                    v
+                },
+                from_osc: quote! {
+                    match o {
+                        OscType::Double(v) => Some(*v),
+                        OscType::Float(v) => Some(*v as f64),
+                        _ => None
+                    }
+                },
+                ..Default::default()
+            },
+            SimpBindingValue {
+                variant_name: &"String",
+                osc_variant: &"String",
+                osc_type: &"String",
+                get_func: quote! {
+                    g.upgrade().map_or(String::new(), |g| g.get().to_string())
+                },
+                set_func: quote! {
+                    s.upgrade().map(|s| s.set(Arc::new(v)));
+                },
+                to_raw: quote! {
+                    v.to_string()
+                },
+                from_raw: quote! {
+                    Arc::new(v)
+                },
+                from_osc: quote! {
+                    match o {
+                        OscType::String(v) => Some(Arc::new(v.clone())),
+                        _ => None
+                    }
+                },
                 ..Default::default()
             },
         ];
 
         let mut access_values = Vec::new();
+        let mut key_values = Vec::new();
         for v in simp.iter() {
             let variant_name = format_ident!("{}", v.variant_name);
             let osc_variant = format_ident!("{}", v.osc_variant);
             let osc_type = format_ident!("{}", v.osc_type);
             let gf = v.get_func.clone();
             let sf = v.set_func.clone();
+            let to_raw = v.to_raw.clone();
+            let from_raw = v.from_raw.clone();
+            let from_osc = v.from_osc.clone();
             let clip = v.clip.clone().unwrap_or(quote! { Default::default() });
             let range = v.range.clone().unwrap_or(quote! { Default::default() });
             access_values.push(quote! {
@@ -546,11 +737,183 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     );
                 }
             });
+
+            //a single key of a key value
+            key_values.push(quote! {
+                crate::param::ParamDataAccess::KeyValueGet(crate::param::ParamDataKeyValueGet::#variant_name(g)) => {
+                    let g = Arc::downgrade(&g);
+                    let _ = self.server.add_node(
+                        oscquery::node::Get::new(
+                            name,
+                            description,
+                            vec![ParamGet::#osc_variant(
+                                ValueBuilder::new(Arc::new(GetFunc::new(move || {
+                                    g.upgrade().and_then(|g| g.get_at(index)).map_or(Default::default(), |v| #to_raw)
+                                })) as _)
+                                .with_clip_mode(#clip)
+                                .with_range(#range)
+                                .build(),
+                            )],
+                        )
+                        .unwrap(),
+                        Some(handle),
+                    );
+                }
+            });
+            key_values.push(quote! {
+                crate::param::ParamDataAccess::KeyValueGetSet(crate::param::ParamDataKeyValueGetSet::#variant_name(gs)) => {
+                    let g = Arc::downgrade(&gs) as Weak<dyn ::sched::binding::ParamBindingKeyValueGet<_>>;
+                    let s = Arc::downgrade(&gs) as Weak<dyn ::sched::binding::ParamBindingKeyValueSet<_>>;
+                    let _ = self.server.add_node(
+                        oscquery::node::GetSet::new(
+                            name,
+                            description,
+                            vec![ParamGetSet::#osc_variant(
+                                ValueBuilder::new(Arc::new(GetSetFuncs::new(
+                                    move || {
+                                        g.upgrade().and_then(|g| g.get_at(index)).map_or(Default::default(), |v| #to_raw)
+                                    },
+                                    move |v: #osc_type| {
+                                        if let Some(s) = s.upgrade() {
+                                            let _ = s.set_at(index, #from_raw);
+                                        }
+                                    },
+                                )) as _)
+                                .with_clip_mode(#clip)
+                                .with_range(#range)
+                                .build(),
+                            )],
+                            None,
+                        )
+                        .unwrap(),
+                        Some(handle),
+                    );
+                }
+            });
+
+            //arrays: the whole array as a list and a node to set a single index
+            access_values.push(quote! {
+                crate::param::ParamDataAccess::KeyValueGet(crate::param::ParamDataKeyValueGet::#variant_name(g)) => {
+                    let g = Arc::downgrade(&g);
+                    let _ = self.server.add_node(
+                        oscquery::node::Get::new(
+                            name,
+                            description,
+                            vec![ParamGet::Array(
+                                ValueBuilder::new(Arc::new(GetFunc::new(move || {
+                                    OscArray {
+                                        content: g.upgrade().map_or(Vec::new(), |g| {
+                                            (0..g.len().unwrap_or(0)).filter_map(|i| g.get_at(i)).map(|v| OscType::#osc_variant(#to_raw)).collect()
+                                        })
+                                    }
+                                })) as _)
+                                .build(),
+                            )],
+                        )
+                        .unwrap(),
+                        Some(handle),
+                    );
+                }
+            });
+            access_values.push(quote! {
+                crate::param::ParamDataAccess::KeyValueGetSet(crate::param::ParamDataKeyValueGetSet::#variant_name(gs)) => {
+                    let g = Arc::downgrade(&gs) as Weak<dyn ::sched::binding::ParamBindingKeyValueGet<_>>;
+                    let s = Arc::downgrade(&gs) as Weak<dyn ::sched::binding::ParamBindingKeyValueSet<_>>;
+                    let len = g.clone();
+                    let _ = self.server.add_node(
+                        oscquery::node::GetSet::new(
+                            name,
+                            description,
+                            vec![ParamGetSet::Array(
+                                ValueBuilder::new(Arc::new(GetFunc::new(move || {
+                                    OscArray {
+                                        content: g.upgrade().map_or(Vec::new(), |g| {
+                                            (0..g.len().unwrap_or(0)).filter_map(|i| g.get_at(i)).map(|v| OscType::#osc_variant(#to_raw)).collect()
+                                        })
+                                    }
+                                })) as _)
+                                .build(),
+                            )],
+                            {
+                                let s = s.clone();
+                                Some(Box::new(OscUpdateFunc::new(move |
+                                    args: &Vec<OscType>,
+                                    _addr: Option<SocketAddr>,
+                                    _time: Option<(u32, u32)>,
+                                    _handle: &NodeHandle,
+                                    | -> Option<OscWriteCallback> {
+                                    //the list is ignored unless it has a valid value for every element of the array
+                                    if let (Some(s), Some(len)) = (s.upgrade(), len.upgrade()) {
+                                        let values: Vec<_> = osc_list(args).iter().filter_map(|o| #from_osc).collect();
+                                        if values.len() == osc_list(args).len() && Some(values.len()) == len.len() {
+                                            for (i, v) in values.into_iter().enumerate() {
+                                                let _ = s.set_at(i, v);
+                                            }
+                                        }
+                                    }
+                                    None
+                                })))
+                            },
+                        )
+                        .unwrap(),
+                        Some(handle),
+                    );
+                    let _ = self.server.add_node(
+                        oscquery::node::Set::new(
+                            "index",
+                            Some("set the value at an index"),
+                            vec![
+                                ParamSet::Long(
+                                    ValueBuilder::new(Arc::new(()) as _)
+                                    .with_clip_mode(ClipMode::Low)
+                                    .with_range(Range::Min(0))
+                                    .build(),
+                                ),
+                                ParamSet::#osc_variant(
+                                    ValueBuilder::new(Arc::new(()) as _)
+                                    .with_clip_mode(#clip)
+                                    .with_range(#range)
+                                    .build(),
+                                ),
+                            ],
+                            Some(Box::new(OscUpdateFunc::new(move |
+                                args: &Vec<OscType>,
+                                _addr: Option<SocketAddr>,
+                                _time: Option<(u32, u32)>,
+                                _handle: &NodeHandle,
+                                | -> Option<OscWriteCallback> {
+                                let index = match args.get(0) {
+                                    Some(OscType::Long(i)) => Some(*i),
+                                    Some(OscType::Int(i)) => Some(*i as i64),
+                                    _ => None,
+                                };
+                                if let (Some(s), Some(i), Some(o)) = (s.upgrade(), index, args.get(1)) {
+                                    if let Some(v) = #from_osc {
+                                        let _ = s.set_at(std::cmp::max(i, 0) as usize, v);
+                                    }
+                                }
+                                None
+                            }))),
+                        )
+                        .unwrap(),
+                        Some(handle),
+                    );
+                }
+            });
         }
 
         oscquery_file.write_all(
             quote! {
                 impl OSCQueryHandler {
+                    //add a node named `name` for the value at `index` of a key value shadow
+                    fn add_param_key_value(&self, shadow: &ParamDataAccess, name: &str, index: usize, handle: ::oscquery::root::NodeHandle) {
+                        let description: Option<&str> = Some(&"value at key");
+                        match shadow {
+                            #(#key_values),*
+                            _ => ()
+                        }
+                    }
+
                     fn add_param_value(&self, shadow: &ParamDataAccess, handle: ::oscquery::root::NodeHandle) {
 
                         fn to_get<T>(weak: &Weak<dyn ::sched::binding::ParamBindingGet<T>>) -> T 
@@ -843,36 +1206,352 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     //instance factory
     {
         let mut entries = Vec::new();
+        //types that have array values and indexing
+        let array_types = ["Bool", "U8", "USize", "ISize", "Float"];
+        //types that have arithmetic operators
+        let numeric_types = ["U8", "USize", "ISize", "Float"];
+        //types that can be cast to each other
+        let cast_types = ["Bool", "U8", "USize", "ISize", "Float"];
 
         for v in variants.iter() {
             let data_type = v.typ.clone();
             let tname = v.type_name.clone();
 
+            if array_types.iter().any(|a| v.var_name == a) {
+                let kv = format_ident!("KeyValue{}", v.var_name);
+                let cname = format!("const::<[{}]>", tname);
+                let cdesc = format!("Constant array of {} values", tname);
+                let mname = format!("val::<[{}]>", tname);
+                let mdesc = format!("Mutable array of {} values", tname);
+                let iname = format!("index::<{}>", tname);
+                let idesc = format!("The {} at index in array, the default value if index is out of range", tname);
+                let mapname = format!("map::<{}>", tname);
+                let mapdesc = format!("Mutable map of names to {} values, keys are sorted", tname);
+                let lname = format!("lookup::<{}>", tname);
+                let ldesc = format!("The {} in map for the String key, the default value if map doesn't have key, keys are the keys of the maps it reads", tname);
+                let chname = format!("choice::<{}>", tname);
+                let chdesc = format!("A random {} from values for every tick, weighted by the Float at the same index in weights, uniform if weights is unbound, the seed defaults to the time", tname);
+                entries.push(
+                    quote! {
+                        let ex: Vec<#data_type> = vec![Default::default(); 4];
+
+                        //constant
+                        let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                            let v: Result<Vec<#data_type>, _> = serde_json::from_value(arg);
+                            match v {
+                                Ok(v) => {
+                                    let g = Arc::new(ArrayConst::new(v)) as Arc<dyn ParamBindingKeyValueGet<#data_type>>;
+                                    Ok(
+                                        (
+                                            g.clone().into(),
+                                            Some(g.into()), //shadow for a const is just the same access
+                                            Default::default()
+                                        )
+                                    )
+                                }
+                                Err(e) => Err(CreateError::InvalidArgs(e.to_string()))
+                            }
+                        });
+                        m.insert(#cname,
+                            ParamFactItem::new(f, #cdesc, Some(serde_json::to_string(&ex).unwrap())
+                        ));
+
+                        //value
+                        let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                            let v: Result<Vec<#data_type>, _> = serde_json::from_value(arg);
+                            match v {
+                                Ok(v) => {
+                                    let gs = Arc::new(::sched::binding::ParamBindingKeyValueGetSet::new(Arc::new(ArrayAtomic::new(v)) as Arc<dyn ParamBindingKeyValue<#data_type>>));
+                                    Ok(
+                                        (
+                                            gs.clone().into(),
+                                            Some(gs.into()),
+                                            Default::default()
+                                        )
+                                    )
+                                }
+                                Err(e) => Err(CreateError::InvalidArgs(e.to_string()))
+                            }
+                        });
+                        m.insert(#mname,
+                            ParamFactItem::new(f, #mdesc, Some(serde_json::to_string(&ex).unwrap())
+                        ));
+
+                        //index
+                        let f: Box<ParamDataFn> = Box::new(|_arg, _queue_sources| {
+                            let array: Arc<BindingSwapKeyValueGet<#data_type>> = Default::default();
+                            let index: Arc<BindingSwapGet<usize>> = Arc::new(BindingSwapGet::new(0));
+                            let mut map = HashMap::new();
+                            map.insert("array", ParamAccess::new_get(ParamGet::#kv(array.clone())));
+                            map.insert("index", ParamAccess::new_get(ParamGet::USize(index.clone())));
+                            let g = Arc::new(IndexGet::new(array, index)) as Arc<dyn ParamBindingGet<#data_type>>;
+                            Ok((g.into(), None, map.into()))
+                        });
+                        m.insert(#iname, ParamFactItem::new(f, #idesc, None));
+
+                        //map
+                        let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                            let v: Vec<#data_type> = map_values(arg)?;
+                            let gs = Arc::new(::sched::binding::ParamBindingKeyValueGetSet::new(Arc::new(ArrayAtomic::new(v)) as Arc<dyn ParamBindingKeyValue<#data_type>>));
+                            Ok(
+                                (
+                                    gs.clone().into(),
+                                    Some(gs.into()),
+                                    Default::default()
+                                )
+                            )
+                        });
+                        let ex: HashMap<&str, #data_type> = [("a", Default::default()), ("b", Default::default())].iter().cloned().collect();
+                        m.insert(#mapname,
+                            ParamFactItem::new(f, #mapdesc, Some(serde_json::to_string(&ex).unwrap())
+                        ));
+
+                        //lookup
+                        let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                            let args = SlotArgs::new(&arg, &["keys", "key"])?;
+                            let keys = map_keys(arg.get("keys").unwrap_or(&JsonValue::Null))?;
+                            let array: Arc<BindingSwapKeyValueGet<#data_type>> = Default::default();
+                            let key: Arc<BindingSwapGet<Arc<String>>> = args.slot("key", Default::default())?;
+                            let mut map = HashMap::new();
+                            map.insert("map", ParamAccess::new_get(ParamGet::#kv(array.clone())));
+                            map.insert("key", ParamAccess::new_get(ParamGet::String(key.clone())));
+                            let g = Arc::new(LookupGet::new(array, key, keys)) as Arc<dyn ParamBindingGet<#data_type>>;
+                            Ok((g.into(), None, map.into()))
+                        });
+                        m.insert(#lname, ParamFactItem::new(f, #ldesc, Some(r#"{"keys": ["a", "b"], "key": "a"}"#.into())));
+
+                        //choice
+                        let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                            let args = SlotArgs::new(&arg, &["seed"])?;
+                            let values: Arc<BindingSwapKeyValueGet<#data_type>> = Default::default();
+                            let weights: Arc<BindingSwapKeyValueGet<Float>> = Default::default();
+                            let seed: Arc<BindingSwapGet<usize>> = args.slot("seed", random::time_seed())?;
+                            let mut map = HashMap::new();
+                            map.insert("values", ParamAccess::new_get(ParamGet::#kv(values.clone())));
+                            map.insert("weights", ParamAccess::new_get(ParamGet::KeyValueFloat(weights.clone())));
+                            map.insert("seed", ParamAccess::new_get(ParamGet::USize(seed.clone())));
+                            let g = Arc::new(ChoiceGet::new(seed, values, weights)) as Arc<dyn ParamBindingGet<#data_type>>;
+                            Ok((g.into(), None, map.into()))
+                        });
+                        m.insert(#chname, ParamFactItem::new(f, #chdesc, Some(r#"{"seed": 1}"#.into())));
+                    }
+                );
+            }
+
+            //casts between every pair of cast types
+            if cast_types.iter().any(|a| v.var_name == a) {
+                let var = v.var_name.clone();
+                for to in variants
+                    .iter()
+                    .filter(|w| w.var_name != v.var_name && cast_types.iter().any(|a| w.var_name == a))
+                {
+                    let to_type = to.typ.clone();
+                    let name = format!("cast::<{}, {}>", tname, to.type_name);
+                    let desc = format!("in converted from {} to {}, clamped to the range of {}, floats are rounded", tname, to.type_name, to.type_name);
+                    entries.push(
+                        quote! {
+                            let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                                let args = SlotArgs::new(&arg, &["in"])?;
+                                let input: Arc<BindingSwapGet<#data_type>> = args.slot("in", Default::default())?;
+                                let mut map = HashMap::new();
+                                map.insert("in", ParamAccess::new_get(ParamGet::#var(input.clone())));
+                                let g = Arc::new(UnaryOp::new(ops::cast::<#data_type, #to_type>, input)) as Arc<dyn ParamBindingGet<#to_type>>;
+                                Ok((g.into(), None, map.into()))
+                            });
+                            m.insert(#name, ParamFactItem::new(f, #desc, None));
+                        }
+                    );
+                }
+            }
+
+            if numeric_types.iter().any(|a| v.var_name == a) {
+                let var = v.var_name.clone();
+                let name = format!("random::<{}>", tname);
+                let max = if v.var_name == "Float" {
+                    quote! { 1.0 }
+                } else {
+                    quote! { <#data_type as Arith>::max_value() }
+                };
+                let desc = format!("A uniform random {} in {} for every tick, max defaults to {}, the seed defaults to the time", tname, if v.var_name == "Float" { "min..max" } else { "min..=max" }, if v.var_name == "Float" { "1" } else { "the largest value" });
+                entries.push(
+                    quote! {
+                        let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                            let args = SlotArgs::new(&arg, &["min", "max", "seed"])?;
+                            let min: Arc<BindingSwapGet<#data_type>> = args.slot("min", Default::default())?;
+                            let max: Arc<BindingSwapGet<#data_type>> = args.slot("max", #max)?;
+                            let seed: Arc<BindingSwapGet<usize>> = args.slot("seed", random::time_seed())?;
+                            let mut map = HashMap::new();
+                            map.insert("min", ParamAccess::new_get(ParamGet::#var(min.clone())));
+                            map.insert("max", ParamAccess::new_get(ParamGet::#var(max.clone())));
+                            map.insert("seed", ParamAccess::new_get(ParamGet::USize(seed.clone())));
+                            let g = Arc::new(RandomGet::new(seed, min, max)) as Arc<dyn ParamBindingGet<#data_type>>;
+                            Ok((g.into(), None, map.into()))
+                        });
+                        m.insert(#name, ParamFactItem::new(f, #desc, Some(r#"{"min": 0, "max": 100, "seed": 1}"#.into())));
+                    }
+                );
+
+                let compare = [
+                    ("eq", "left == right", quote! { <#data_type as PartialEq>::eq }),
+                    ("ne", "left != right", quote! { <#data_type as PartialEq>::ne }),
+                    ("lt", "left < right", quote! { <#data_type as PartialOrd>::lt }),
+                    ("le", "left <= right", quote! { <#data_type as PartialOrd>::le }),
+                    ("gt", "left > right", quote! { <#data_type as PartialOrd>::gt }),
+                    ("ge", "left >= right", quote! { <#data_type as PartialOrd>::ge }),
+                ];
+                for (op, desc, func) in compare.iter() {
+                    let name = format!("{}::<{}>", op, tname);
+                    let desc = format!("{}, {} values", desc, tname);
+                    entries.push(
+                        quote! {
+                            let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                                let args = SlotArgs::new(&arg, &["left", "right"])?;
+                                let left: Arc<BindingSwapGet<#data_type>> = args.slot("left", Default::default())?;
+                                let right: Arc<BindingSwapGet<#data_type>> = args.slot("right", Default::default())?;
+                                let mut map = HashMap::new();
+                                map.insert("left", ParamAccess::new_get(ParamGet::#var(left.clone())));
+                                map.insert("right", ParamAccess::new_get(ParamGet::#var(right.clone())));
+                                let g = Arc::new(BinaryOp::new(|l: #data_type, r: #data_type| #func(&l, &r), left, right)) as Arc<dyn ParamBindingGet<bool>>;
+                                Ok((g.into(), None, map.into()))
+                            });
+                            m.insert(#name, ParamFactItem::new(f, #desc, Some(r#"{"right": 0}"#.into())));
+                        }
+                    );
+                }
+
+                let binary = [
+                    ("add", "left + right", quote! { <#data_type as Arith>::add }),
+                    ("sub", "left - right", quote! { <#data_type as Arith>::sub }),
+                    ("mul", "left * right", quote! { <#data_type as Arith>::mul }),
+                    ("div", "left / right, zero if right is zero", quote! { <#data_type as Arith>::div }),
+                    ("rem", "left % right, zero if right is zero", quote! { <#data_type as Arith>::rem }),
+                    ("min", "The lesser of left and right", quote! { ops::min::<#data_type> }),
+                    ("max", "The greater of left and right", quote! { ops::max::<#data_type> }),
+                ];
+                for (op, desc, func) in binary.iter() {
+                    let name = format!("{}::<{}>", op, tname);
+                    let desc = format!("{}, {} values", desc, tname);
+                    entries.push(
+                        quote! {
+                            let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                                let args = SlotArgs::new(&arg, &["left", "right"])?;
+                                let left: Arc<BindingSwapGet<#data_type>> = args.slot("left", Default::default())?;
+                                let right: Arc<BindingSwapGet<#data_type>> = args.slot("right", Default::default())?;
+                                let mut map = HashMap::new();
+                                map.insert("left", ParamAccess::new_get(ParamGet::#var(left.clone())));
+                                map.insert("right", ParamAccess::new_get(ParamGet::#var(right.clone())));
+                                let g = Arc::new(BinaryOp::new(#func, left, right)) as Arc<dyn ParamBindingGet<#data_type>>;
+                                Ok((g.into(), None, map.into()))
+                            });
+                            m.insert(#name, ParamFactItem::new(f, #desc, Some(r#"{"right": 1}"#.into())));
+                        }
+                    );
+                }
+
+                let unary = [
+                    ("neg", "-in", quote! { <#data_type as Arith>::neg }),
+                    ("abs", "The absolute value of in", quote! { <#data_type as Arith>::abs }),
+                ];
+                for (op, desc, func) in unary.iter() {
+                    let name = format!("{}::<{}>", op, tname);
+                    let desc = format!("{}, {} values", desc, tname);
+                    entries.push(
+                        quote! {
+                            let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                                let args = SlotArgs::new(&arg, &["in"])?;
+                                let input: Arc<BindingSwapGet<#data_type>> = args.slot("in", Default::default())?;
+                                let mut map = HashMap::new();
+                                map.insert("in", ParamAccess::new_get(ParamGet::#var(input.clone())));
+                                let g = Arc::new(UnaryOp::new(#func, input)) as Arc<dyn ParamBindingGet<#data_type>>;
+                                Ok((g.into(), None, map.into()))
+                            });
+                            m.insert(#name, ParamFactItem::new(f, #desc, None));
+                        }
+                    );
+                }
+
+                let name = format!("clamp::<{}>", tname);
+                let desc = format!("in limited to min..=max, {} values, max defaults to the largest value", tname);
+                entries.push(
+                    quote! {
+                        let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                            let args = SlotArgs::new(&arg, &["in", "min", "max"])?;
+                            let input: Arc<BindingSwapGet<#data_type>> = args.slot("in", Default::default())?;
+                            let min: Arc<BindingSwapGet<#data_type>> = args.slot("min", Default::default())?;
+                            let max: Arc<BindingSwapGet<#data_type>> = args.slot("max", <#data_type as Arith>::max_value())?;
+                            let mut map = HashMap::new();
+                            map.insert("in", ParamAccess::new_get(ParamGet::#var(input.clone())));
+                            map.insert("min", ParamAccess::new_get(ParamGet::#var(min.clone())));
+                            map.insert("max", ParamAccess::new_get(ParamGet::#var(max.clone())));
+                            let g = Arc::new(TernaryOp::new(ops::clamp::<#data_type>, input, min, max)) as Arc<dyn ParamBindingGet<#data_type>>;
+                            Ok((g.into(), None, map.into()))
+                        });
+                        m.insert(#name, ParamFactItem::new(f, #desc, Some(r#"{"min": 0, "max": 1}"#.into())));
+                    }
+                );
+            }
+
+            //select, for every type that can be used in a ParamGet
+            if array_types.iter().any(|a| v.var_name == a) || v.var_name == "String" {
+                let var = v.var_name.clone();
+                let name = format!("select::<{}>", tname);
+                let desc = format!("on_true if cond is true, otherwise on_false, {} values", tname);
+                entries.push(
+                    quote! {
+                        let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                            let args = SlotArgs::new(&arg, &["cond", "on_true", "on_false"])?;
+                            let cond: Arc<BindingSwapGet<bool>> = args.slot("cond", false)?;
+                            let on_true: Arc<BindingSwapGet<#data_type>> = args.slot("on_true", Default::default())?;
+                            let on_false: Arc<BindingSwapGet<#data_type>> = args.slot("on_false", Default::default())?;
+                            let mut map = HashMap::new();
+                            map.insert("cond", ParamAccess::new_get(ParamGet::Bool(cond.clone())));
+                            map.insert("on_true", ParamAccess::new_get(ParamGet::#var(on_true.clone())));
+                            map.insert("on_false", ParamAccess::new_get(ParamGet::#var(on_false.clone())));
+                            let g = Arc::new(TernaryOp::new(ops::select::<#data_type>, cond, on_true, on_false)) as Arc<dyn ParamBindingGet<#data_type>>;
+                            Ok((g.into(), None, map.into()))
+                        });
+                        m.insert(#name, ParamFactItem::new(f, #desc, None));
+                    }
+                );
+            }
+
             let cname = format!("const::<{}>", tname);
             let cdesc = format!("Constant {} value", tname);
 
             let mname = format!("val::<{}>", tname);
             let mdesc = format!("Mutable {} value", tname);
 
+            //strings cannot be stored atomically, replaced strings are dropped in the dispose thread
+            let (const_queue_sources, const_value, value) = if v.var_name == "String" {
+                (
+                    quote! { queue_sources },
+                    quote! { StringValue::new(v, queue_sources.set_queue()) },
+                    quote! { StringValue::new(v, queue_sources.set_queue()) }
+                )
+            } else {
+                (quote! { _queue_sources }, quote! { v }, quote! { Atomic::new(v) })
+            };
+
             //consts and values
             entries.push(
                 quote! {
                     let ex: #data_type = Default::default();
 
                     //constant
-                    let f: Box<ParamDataFn> = Box::new(|arg| {
+                    let f: Box<ParamDataFn> = Box::new(|arg, #const_queue_sources| {
                         let v: Result<#data_type, _> = serde_json::from_value(arg);
-                        if let Ok(v) = v {
-                            let g = Arc::new(v) as Arc<dyn ParamBindingGet<#data_type>>;
-                            Ok(
-                                (
-                                    g.clone().into(),
-                                    Some(g.into()), //shadow for a const is just the same access
-                                    Default::default()
+                        match v {
+                            Ok(v) => {
+                                let g = Arc::new(#const_value) as Arc<dyn ParamBindingGet<#data_type>>;
+                                Ok(
+                                    (
+                                        g.clone().into(),
+                                        Some(g.into()), //shadow for a const is just the same access
+                                        Default::default()
+                                    )
                                 )
-                            )
-                        } else {
-                            Err(CreateError::InvalidArgs)
+                            }
+                            Err(e) => Err(CreateError::InvalidArgs(e.to_string()))
                         }
                     });
                     m.insert(#cname, 
@@ -880,19 +1559,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     ));
 
                     //value
-                    let f: Box<ParamDataFn> = Box::new(|arg| {
+                    let f: Box<ParamDataFn> = Box::new(|arg, queue_sources| {
                         let v: Result<#data_type, _> = serde_json::from_value(arg);
-                        if let Ok(v) = v {
-                            let gs = Arc::new(::sched::binding::ParamBindingGetSet::new(Arc::new(Atomic::new(v)) as Arc<dyn ParamBinding<#data_type>>));
-                            Ok(
-                                (
-                                    gs.clone().into(),
-                                    Some(gs.into()), //TODO shadow access should be queued
-                                    Default::default()
+                        match v {
+                            Ok(v) => {
+                                let value = Arc::new(#value) as Arc<dyn ParamBinding<#data_type>>;
+                                let gs = Arc::new(::sched::binding::ParamBindingGetSet::new(value.clone()));
+                                //the shadow queues sets for the scheduler thread
+                                let shadow = Arc::new(::sched::binding::ParamBindingGetSet::new(Arc::new(QueuedValue::new(value, queue_sources.set_queue())) as Arc<dyn ParamBinding<#data_type>>));
+                                Ok(
+                                    (
+                                        gs.into(),
+                                        Some(shadow.into()),
+                                        Default::default()
+                                    )
                                 )
-                            )
-                        } else {
-                            Err(CreateError::InvalidArgs)
+                            }
+                            Err(e) => Err(CreateError::InvalidArgs(e.to_string()))
                         }
                     });
                     m.insert(#mname, 
@@ -902,6 +1585,175 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             );
         }
 
+        //boolean logic
+        let binary = [
+            ("and", "left && right", quote! { |l: bool, r: bool| l && r }),
+            ("or", "left || right", quote! { |l: bool, r: bool| l || r }),
+            ("xor", "left ^ right", quote! { |l: bool, r: bool| l ^ r }),
+        ];
+        for (name, desc, func) in binary.iter() {
+            entries.push(
+                quote! {
+                    let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                        let args = SlotArgs::new(&arg, &["left", "right"])?;
+                        let left: Arc<BindingSwapGet<bool>> = args.slot("left", false)?;
+                        let right: Arc<BindingSwapGet<bool>> = args.slot("right", false)?;
+                        let mut map = HashMap::new();
+                        map.insert("left", ParamAccess::new_get(ParamGet::Bool(left.clone())));
+                        map.insert("right", ParamAccess::new_get(ParamGet::Bool(right.clone())));
+                        let g = Arc::new(BinaryOp::new(#func, left, right)) as Arc<dyn ParamBindingGet<bool>>;
+                        Ok((g.into(), None, map.into()))
+                    });
+                    m.insert(#name, ParamFactItem::new(f, #desc, None));
+                }
+            );
+        }
+        entries.push(
+            quote! {
+                let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                    let args = SlotArgs::new(&arg, &["in"])?;
+                    let input: Arc<BindingSwapGet<bool>> = args.slot("in", false)?;
+                    let mut map = HashMap::new();
+                    map.insert("in", ParamAccess::new_get(ParamGet::Bool(input.clone())));
+                    let g = Arc::new(UnaryOp::new(|v: bool| !v, input)) as Arc<dyn ParamBindingGet<bool>>;
+                    Ok((g.into(), None, map.into()))
+                });
+                m.insert("not", ParamFactItem::new(f, "!in", None));
+            }
+        );
+
+        //rhythms
+        entries.push(
+            quote! {
+                let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                    let args = SlotArgs::new(&arg, &["steps", "pulses", "rotation", "index"])?;
+                    let steps: Arc<BindingSwapGet<usize>> = args.slot("steps", 16)?;
+                    let pulses: Arc<BindingSwapGet<usize>> = args.slot("pulses", 4)?;
+                    let rotation: Arc<BindingSwapGet<usize>> = args.slot("rotation", 0)?;
+                    let index: Arc<BindingSwapGet<usize>> = args.slot("index", 0)?;
+                    let mut map = HashMap::new();
+                    map.insert("steps", ParamAccess::new_get(ParamGet::USize(steps.clone())));
+                    map.insert("pulses", ParamAccess::new_get(ParamGet::USize(pulses.clone())));
+                    map.insert("rotation", ParamAccess::new_get(ParamGet::USize(rotation.clone())));
+                    map.insert("index", ParamAccess::new_get(ParamGet::USize(index.clone())));
+                    let g = Arc::new(EuclidGet::new(steps, pulses, rotation, index)) as Arc<dyn ParamBindingGet<bool>>;
+                    Ok((g.into(), None, map.into()))
+                });
+                m.insert("euclid",
+                    ParamFactItem::new(
+                        f,
+                        "true if step index, wrapped to steps, of pulses distributed evenly over steps, delayed by rotation steps, is a pulse",
+                        Some(r#"{"steps": 8, "pulses": 3}"#.into())
+                ));
+            }
+        );
+
+        //scales
+        entries.push(
+            quote! {
+                let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                    let args = SlotArgs::new(&arg, &["scale", "degree", "root", "octave"])?;
+                    let scale = Scale::from_json(arg.get("scale"))?;
+                    let degree: Arc<BindingSwapGet<isize>> = args.slot("degree", 0)?;
+                    let root: Arc<BindingSwapGet<u8>> = args.slot("root", 60)?;
+                    let octave: Arc<BindingSwapGet<isize>> = args.slot("octave", 0)?;
+                    let mut map = HashMap::new();
+                    map.insert("degree", ParamAccess::new_get(ParamGet::ISize(degree.clone())));
+                    map.insert("root", ParamAccess::new_get(ParamGet::U8(root.clone())));
+                    map.insert("octave", ParamAccess::new_get(ParamGet::ISize(octave.clone())));
+                    let g = Arc::new(ScaleQuantize::new(scale, degree, root, octave)) as Arc<dyn ParamBindingGet<u8>>;
+                    Ok((g.into(), None, map.into()))
+                });
+                m.insert("scale_quantize",
+                    ParamFactItem::new(
+                        f,
+                        "MIDI note for degree of scale from root, transposed by octave, scale is a built in scale name or an array of semitones, defaults to major",
+                        Some(r#"{"scale": "dorian", "root": 62}"#.into())
+                ));
+
+                let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                    let args = SlotArgs::new(&arg, &["scale", "in", "root"])?;
+                    let scale = Scale::from_json(arg.get("scale"))?;
+                    let input: Arc<BindingSwapGet<u8>> = args.slot("in", 0)?;
+                    let root: Arc<BindingSwapGet<u8>> = args.slot("root", 60)?;
+                    let mut map = HashMap::new();
+                    map.insert("in", ParamAccess::new_get(ParamGet::U8(input.clone())));
+                    map.insert("root", ParamAccess::new_get(ParamGet::U8(root.clone())));
+                    let g = Arc::new(ScaleSnap::new(scale, input, root)) as Arc<dyn ParamBindingGet<u8>>;
+                    Ok((g.into(), None, map.into()))
+                });
+                m.insert("scale_quantize::snap",
+                    ParamFactItem::new(
+                        f,
+                        "The MIDI note in scale from root nearest to the note in, the lower on a tie, scale is a built in scale name or an array of semitones, defaults to major",
+                        Some(r#"{"scale": [0, 3, 5, 7, 10], "root": 60}"#.into())
+                ));
+            }
+        );
+
+        //modulation
+        let shapes = [
+            ("lfo::sine", quote! { Shape::Sine }, "Sine LFO"),
+            ("lfo::triangle", quote! { Shape::Triangle }, "Triangle LFO"),
+            ("lfo::saw", quote! { Shape::Saw }, "Rising saw LFO"),
+            ("lfo::square", quote! { Shape::Square }, "Square LFO"),
+            ("lfo::sample_hold", quote! { Shape::SampleHold }, "Sample and hold LFO, a random value for each period, the seed defaults to the time"),
+        ];
+        for (name, shape, desc) in shapes.iter() {
+            let desc = format!("{}, offset + depth * wave, the wave ranges from -1 to 1 every period_ticks, phase is in periods", desc);
+            entries.push(
+                quote! {
+                    let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                        let args = SlotArgs::new(&arg, &["period_ticks", "phase", "depth", "offset", "seed"])?;
+                        let period_ticks: Arc<BindingSwapGet<usize>> = args.slot("period_ticks", 960)?;
+                        let phase: Arc<BindingSwapGet<Float>> = args.slot("phase", 0.0)?;
+                        let depth: Arc<BindingSwapGet<Float>> = args.slot("depth", 1.0)?;
+                        let offset: Arc<BindingSwapGet<Float>> = args.slot("offset", 0.0)?;
+                        let seed: Arc<BindingSwapGet<usize>> = args.slot("seed", random::time_seed())?;
+                        let mut map = HashMap::new();
+                        map.insert("period_ticks", ParamAccess::new_get(ParamGet::USize(period_ticks.clone())));
+                        map.insert("phase", ParamAccess::new_get(ParamGet::Float(phase.clone())));
+                        map.insert("depth", ParamAccess::new_get(ParamGet::Float(depth.clone())));
+                        map.insert("offset", ParamAccess::new_get(ParamGet::Float(offset.clone())));
+                        map.insert("seed", ParamAccess::new_get(ParamGet::USize(seed.clone())));
+                        let g = Arc::new(Lfo::new(#shape, period_ticks, phase, depth, offset, seed)) as Arc<dyn ParamBindingGet<Float>>;
+                        Ok((g.into(), None, map.into()))
+                    });
+                    m.insert(#name, ParamFactItem::new(f, #desc, Some(r#"{"period_ticks": 960, "depth": 63.5, "offset": 63.5}"#.into())));
+                }
+            );
+        }
+        entries.push(
+            quote! {
+                let f: Box<ParamDataFn> = Box::new(|arg, _queue_sources| {
+                    let args = SlotArgs::new(&arg, &["trigger", "attack_ticks", "decay_ticks", "sustain", "release_ticks", "depth", "offset"])?;
+                    let trigger: Arc<BindingSwapGet<bool>> = args.slot("trigger", false)?;
+                    let attack_ticks: Arc<BindingSwapGet<usize>> = args.slot("attack_ticks", 0)?;
+                    let decay_ticks: Arc<BindingSwapGet<usize>> = args.slot("decay_ticks", 0)?;
+                    let sustain: Arc<BindingSwapGet<Float>> = args.slot("sustain", 1.0)?;
+                    let release_ticks: Arc<BindingSwapGet<usize>> = args.slot("release_ticks", 0)?;
+                    let depth: Arc<BindingSwapGet<Float>> = args.slot("depth", 1.0)?;
+                    let offset: Arc<BindingSwapGet<Float>> = args.slot("offset", 0.0)?;
+                    let mut map = HashMap::new();
+                    map.insert("trigger", ParamAccess::new_get(ParamGet::Bool(trigger.clone())));
+                    map.insert("attack_ticks", ParamAccess::new_get(ParamGet::USize(attack_ticks.clone())));
+                    map.insert("decay_ticks", ParamAccess::new_get(ParamGet::USize(decay_ticks.clone())));
+                    map.insert("sustain", ParamAccess::new_get(ParamGet::Float(sustain.clone())));
+                    map.insert("release_ticks", ParamAccess::new_get(ParamGet::USize(release_ticks.clone())));
+                    map.insert("depth", ParamAccess::new_get(ParamGet::Float(depth.clone())));
+                    map.insert("offset", ParamAccess::new_get(ParamGet::Float(offset.clone())));
+                    let g = Arc::new(Envelope::new(trigger, attack_ticks, decay_ticks, sustain, release_ticks, depth, offset)) as Arc<dyn ParamBindingGet<Float>>;
+                    Ok((g.into(), None, map.into()))
+                });
+                m.insert("envelope",
+                    ParamFactItem::new(
+                        f,
+                        "ADSR envelope, offset + depth * level, restarts when trigger becomes true and releases when it becomes false",
+                        Some(r#"{"attack_ticks": 10, "decay_ticks": 100, "sustain": 0.5, "release_ticks": 200}"#.into())
+                ));
+            }
+        );
+
         instance_factory_file.write_all(
             quote! {
                 lazy_static::lazy_static! {
@@ -0,0 +1,132 @@
+//! Structural changes that are applied in the scheduler thread, at a specific tick.
+
+use crate::{
+    graph::{ChildrenUUIDs, GraphItem},
+    param::{BindingError, Param, ParamMapGet},
+};
+use sched::{
+    atomic::{Atomic, Ordering},
+    event::{EventEval, EventEvalContext},
+};
+use std::sync::Arc;
+
+/// A change to the graph or the param bindings.
+pub enum Change {
+    /// Swap the children of `item`, after evaluation `children` holds the replaced children.
+    ChildrenSwap {
+        item: Arc<GraphItem>,
+        children: Option<ChildrenUUIDs>,
+    },
+    /// Bind `param` to the parameter `name` of `owner`, after evaluation `error` holds the reason
+    /// if the binding failed.
+    Bind {
+        owner: Arc<dyn ParamMapGet + Send + Sync>,
+        name: String,
+        param: Arc<Param>,
+        error: Option<BindingError>,
+    },
+    /// Unbind the parameter `name` of `owner`, after evaluation `unbound` holds the old binding.
+    Unbind {
+        owner: Arc<dyn ParamMapGet + Send + Sync>,
+        name: String,
+        unbound: Option<Arc<Param>>,
+    },
+    /// Activate a root with the gate of its event, scheduled at the same tick.
+    /// After evaluation `gate` holds the previous gate, if any.
+    RootActivate {
+        item: Arc<GraphItem>,
+        gate: Option<Arc<Atomic<bool>>>,
+    },
+    /// Deactivate a root.
+    RootDeactivate { item: Arc<GraphItem> },
+}
+
+/// An event that applies a list of changes, all at the same tick.
+///
+/// # Remarks
+///
+/// * Anything replaced by a change is kept in the event, the executor hands evaluated events to
+/// the dispose thread so nothing is freed in the scheduler thread.
+/// * Changes that failed are reported when the event is dropped, in the dispose thread.
+/// * Nothing is applied if the gate is closed before the event is evaluated.
+pub struct ChangeEvent {
+    changes: Vec<Change>,
+    gate: Arc<Atomic<bool>>,
+}
+
+impl ChangeEvent {
+    pub fn new(changes: Vec<Change>) -> Self {
+        Self {
+            changes,
+            gate: Arc::new(Atomic::new(true)),
+        }
+    }
+
+    /// Get the gate, storing `false` cancels the changes if they haven't been applied yet.
+    pub fn gate(&self) -> Arc<Atomic<bool>> {
+        self.gate.clone()
+    }
+}
+
+impl EventEval for ChangeEvent {
+    fn event_eval(&mut self, _context: &mut dyn EventEvalContext) {
+        if !self.gate.load(Ordering::Acquire) {
+            return;
+        }
+        for change in self.changes.iter_mut() {
+            match change {
+                Change::ChildrenSwap { item, children } => {
+                    if let Some(new) = children.take() {
+                        //keep whatever we get back, old children or the rejected new ones
+                        *children = Some(match item.children_swap(new) {
+                            Ok(old) => old,
+                            Err(new) => new,
+                        });
+                    }
+                }
+                Change::Bind {
+                    owner,
+                    name,
+                    param,
+                    error,
+                } => {
+                    *error = owner.params().try_bind(name, param.clone()).err();
+                }
+                Change::Unbind {
+                    owner,
+                    name,
+                    unbound,
+                } => {
+                    *unbound = owner.params().unbind(name);
+                }
+                Change::RootActivate { item, gate } => {
+                    if let Some(g) = gate.take() {
+                        *gate = item.root_activate(g);
+                    }
+                }
+                Change::RootDeactivate { item } => item.root_deactivate(),
+            }
+        }
+    }
+}
+
+impl Drop for ChangeEvent {
+    fn drop(&mut self) {
+        for change in self.changes.iter() {
+            if let Change::Bind {
+                name,
+                param,
+                error: Some(error),
+                ..
+            } = change
+            {
+                eprintln!(
+                    "scheduled bind of {} to {} failed {:?}",
+                    param.uuid(),
+                    name,
+                    error
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Cycle detection for param bindings and graph children.
+
+use std::collections::HashSet;
+
+/// Would making `from` depend on `to` create a cycle?
+///
+/// # Arguments
+///
+/// * `from` - the item that would depend on `to`.
+/// * `to` - the item that `from` would depend on.
+/// * `edges` - get the ids of the items that the given item already depends on.
+pub fn creates_cycle<F, I>(from: &uuid::Uuid, to: &uuid::Uuid, edges: F) -> bool
+where
+    F: Fn(&uuid::Uuid) -> I,
+    I: IntoIterator<Item = uuid::Uuid>,
+{
+    //there is a cycle if `from` can be reached from `to`
+    let mut visited = HashSet::new();
+    let mut stack = vec![to.clone()];
+    while let Some(id) = stack.pop() {
+        if &id == from {
+            return true;
+        }
+        if visited.insert(id) {
+            stack.extend(edges(&id));
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn detects() {
+        let a = uuid::Uuid::new_v4();
+        let b = uuid::Uuid::new_v4();
+        let c = uuid::Uuid::new_v4();
+        let d = uuid::Uuid::new_v4();
+
+        //a -> b -> c, d -> c
+        let mut deps: HashMap<uuid::Uuid, Vec<uuid::Uuid>> = HashMap::new();
+        deps.insert(a, vec![b]);
+        deps.insert(b, vec![c]);
+        deps.insert(d, vec![c]);
+        let edges = |id: &uuid::Uuid| deps.get(id).cloned().unwrap_or_default();
+
+        assert!(creates_cycle(&a, &a, edges));
+        assert!(creates_cycle(&c, &a, edges));
+        assert!(creates_cycle(&b, &a, edges));
+        assert!(!creates_cycle(&a, &c, edges));
+        assert!(!creates_cycle(&a, &d, edges));
+        assert!(!creates_cycle(&d, &a, edges));
+        assert!(creates_cycle(&c, &d, edges));
+    }
+}
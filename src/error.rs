@@ -1,7 +1,70 @@
-#[derive(Debug, Clone, Copy)]
+use crate::param::BindingError;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
 pub enum CreateError {
     TypeNotFound,
-    InvalidArgs,
+    /// The arguments could not be used, with details.
+    InvalidArgs(String),
+}
+
+/// Errors in handling commands.
+#[derive(Debug, Clone, Serialize)]
+pub enum CommandError {
+    /// The command string could not be deserialized.
+    Parse(String),
+    /// Reading or writing a file failed.
+    Io(String),
+    /// There are no commands to undo.
+    NothingToUndo,
+    /// There are no undone commands to redo.
+    NothingToRedo,
+    /// The session document version is not supported.
+    SessionVersion(usize),
+    /// An item with the given id already exists.
+    IdExists(uuid::Uuid),
+    /// No param with the given id.
+    ParamNotFound(uuid::Uuid),
+    /// No graph item with the given id.
+    GraphItemNotFound(uuid::Uuid),
+    /// No param or graph item with the given id.
+    IdNotFound(uuid::Uuid),
+    /// No alias with the given name.
+    AliasNotFound(String),
+    /// The alias name is not valid, it must be usable as an OSC address part and not be a uuid.
+    InvalidAlias(String),
+    /// The graph item cannot have children.
+    NoChildren(uuid::Uuid),
+    /// Failed to bind, or unbind, `param_id` to the parameter `param_name` of `owner`.
+    Binding {
+        owner: uuid::Uuid,
+        param_name: String,
+        param_id: Option<uuid::Uuid>,
+        error: BindingError,
+    },
+    /// Making `from` depend on `to` would create a cycle.
+    Cycle { from: uuid::Uuid, to: uuid::Uuid },
+    /// Failed to create an instance of `type_name`.
+    Create {
+        id: uuid::Uuid,
+        type_name: String,
+        error: CreateError,
+    },
+    /// The command is not allowed in an atomic batch.
+    NotAllowedInAtomicBatch,
+    /// The command cannot be scheduled.
+    NotSchedulable,
+    /// The graph item is not a root.
+    NotRoot(uuid::Uuid),
+    /// The root is not active so there is no start tick to schedule relative to.
+    RootNotActive(uuid::Uuid),
+    /// The item is used by a scheduled change that will be applied at `tick`, so it cannot be
+    /// removed until then.
+    Pending { id: uuid::Uuid, tick: usize },
+    /// The change event could not be added to the schedule.
+    ScheduleFailed,
+    /// Errors from a batch, paired with the index of the command that caused them.
+    Batch(Vec<(usize, CommandError)>),
 }
 
 impl std::fmt::Display for CreateError {
@@ -11,3 +74,11 @@ impl std::fmt::Display for CreateError {
 }
 
 impl std::error::Error for CreateError {}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for CommandError {}
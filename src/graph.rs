@@ -2,14 +2,14 @@
 
 use crate::{
     graph::children::{SwapChildren, SwapChildrenContainer},
-    param::{ParamHashMap, ParamMapGet},
+    param::{modulation::with_eval_tick, ParamHashMap, ParamMapGet},
 };
 use sched::{
     atomic::{Atomic, Ordering},
     binding::{swap::BindingSwapSet, ParamBindingGet},
     event::{
         gate::{ArcMutexEvent, GateEvent},
-        EventContainer,
+        EventContainer, EventEval, EventEvalContext,
     },
     graph::{
         node_wrapper::GraphNodeWrapper, root_wrapper::GraphRootWrapper, GraphLeafExec,
@@ -25,6 +25,9 @@ pub mod factory;
 
 pub type ChildrenUUIDs = (Arc<crate::graph::children::Children>, Vec<uuid::Uuid>);
 
+//evaluates a root with the tick from its context published for the params it reads
+struct EvalTickRoot<E>(E);
+
 #[derive(Default)]
 pub struct SwapChildrenWithUUIDs {
     children: Arc<SwapChildren>,
@@ -41,6 +44,7 @@ pub enum GraphItem {
         params: ParamHashMap,
         children: Mutex<SwapChildrenWithUUIDs>,
         active_gate: Mutex<Option<Arc<Atomic<bool>>>>,
+        args: Option<serde_json::Value>,
     },
     ///Node can have children.
     Node {
@@ -49,6 +53,7 @@ pub enum GraphItem {
         inner: GraphNodeContainer,
         params: ParamHashMap,
         children: Mutex<SwapChildrenWithUUIDs>,
+        args: Option<serde_json::Value>,
     },
     ///Leaf is a terminal node, cannot have children.
     Leaf {
@@ -56,6 +61,7 @@ pub enum GraphItem {
         uuid: uuid::Uuid,
         inner: GraphNodeContainer,
         params: ParamHashMap,
+        args: Option<serde_json::Value>,
     },
 }
 
@@ -94,6 +100,7 @@ impl GraphItem {
             uuid: id.unwrap_or_else(|| uuid::Uuid::new_v4()),
             inner: GraphNodeWrapper::new(exec, sched::graph::children::empty::Children).into(),
             params: params.into(),
+            args: None,
         }
     }
 
@@ -123,6 +130,7 @@ impl GraphItem {
                 .into(),
             params,
             children: Mutex::new(children),
+            args: None,
         }
     }
 
@@ -148,13 +156,33 @@ impl GraphItem {
         Self::Root {
             type_name,
             uuid: id.unwrap_or_else(|| uuid::Uuid::new_v4()),
-            inner: Arc::new(Mutex::new(GraphRootWrapper::new(
+            inner: Arc::new(Mutex::new(EvalTickRoot(GraphRootWrapper::new(
                 exec,
                 SwapChildrenContainer::new(children.children()),
-            ))),
+            )))),
             params,
             children: Mutex::new(children),
             active_gate: Mutex::new(None),
+            args: None,
+        }
+    }
+
+    /// Record the arguments that this item was created with.
+    pub fn with_args(mut self, args: Option<serde_json::Value>) -> Self {
+        match &mut self {
+            Self::Root { args: a, .. }
+            | Self::Node { args: a, .. }
+            | Self::Leaf { args: a, .. } => *a = args,
+        }
+        self
+    }
+
+    /// Get the arguments this item was created with, if they were recorded.
+    pub fn args(&self) -> Option<&serde_json::Value> {
+        match self {
+            Self::Root { args, .. } | Self::Node { args, .. } | Self::Leaf { args, .. } => {
+                args.as_ref()
+            }
         }
     }
 
@@ -163,21 +191,44 @@ impl GraphItem {
     /// # Remarks
     /// * If this root is already active, this will halt its processing.
     pub fn root_event(&self) -> Option<EventContainer> {
+        self.root_event_gate().map(|(e, g)| {
+            self.root_activate(g);
+            e
+        })
+    }
+
+    /// Get an `EventContainer` for this node, if it is a Root node, and the gate that activates it.
+    ///
+    /// # Remarks
+    /// * This does not halt any current processing, the new event is not tracked as active until
+    /// its gate is passed to `root_activate`.
+    pub fn root_event_gate(&self) -> Option<(EventContainer, Arc<Atomic<bool>>)> {
         match self {
-            Self::Root {
-                ref active_gate,
-                inner,
-                ..
-            } => {
+            Self::Root { inner, .. } => {
                 let g: Arc<Atomic<bool>> = Arc::new(Atomic::new(true));
                 let v = EventContainer::new(GateEvent::new(
                     g.clone() as Arc<dyn ParamBindingGet<bool>>,
                     inner.clone(),
                 ));
-                if let Some(g) = active_gate.lock().replace(g) {
+                Some((v, g))
+            }
+            Self::Node { .. } | Self::Leaf { .. } => None,
+        }
+    }
+
+    /// Make `gate` the active gate for this root, halting any previous processing.
+    ///
+    /// Returns the previous gate, if there was one.
+    pub fn root_activate(&self, gate: Arc<Atomic<bool>>) -> Option<Arc<Atomic<bool>>> {
+        match self {
+            Self::Root {
+                ref active_gate, ..
+            } => {
+                let prev = active_gate.lock().replace(gate);
+                if let Some(g) = &prev {
                     g.store(false, Ordering::Release);
                 }
-                Some(v)
+                prev
             }
             Self::Node { .. } | Self::Leaf { .. } => None,
         }
@@ -258,6 +309,15 @@ impl GraphItem {
         }
     }
 
+    ///Get the kind of item: "root", "node" or "leaf".
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Self::Root { .. } => "root",
+            Self::Node { .. } => "node",
+            Self::Leaf { .. } => "leaf",
+        }
+    }
+
     ///Get the uuid for this item.
     pub fn uuid(&self) -> uuid::Uuid {
         match self {
@@ -296,6 +356,13 @@ impl SwapChildrenWithUUIDs {
     }
 }
 
+impl<E: EventEval> EventEval for EvalTickRoot<E> {
+    fn event_eval(&mut self, context: &mut dyn EventEvalContext) {
+        let inner = &mut self.0;
+        with_eval_tick(context.tick_now(), || inner.event_eval(context))
+    }
+}
+
 impl Drop for GraphItem {
     fn drop(&mut self) {
         self.root_deactivate()
@@ -1,8 +1,10 @@
+use crate::param::modulation::with_eval_tick;
 use sched::{
     binding::{swap::BindingSwapSet, ParamBindingSet},
     event::EventEvalContext,
     graph::{ChildCount, GraphChildExec, GraphNode, GraphNodeContainer},
     mutex::Mutex,
+    tick::TickContext,
 };
 use std::{ops::DerefMut, sync::Arc};
 
@@ -59,7 +61,8 @@ impl GraphChildExec for SwapChildren {
     }
 
     fn child_exec_range(&self, context: &mut dyn EventEvalContext, range: core::ops::Range<usize>) {
-        match self.children.lock().as_ref() {
+        //so that modulation params read by the children see the tick they are evaluated at
+        with_eval_tick(context.tick_now(), || match self.children.lock().as_ref() {
             Children::None => (),
             Children::NChild { child } => {
                 for i in range {
@@ -75,7 +78,7 @@ impl GraphChildExec for SwapChildren {
                     c.node_exec(context);
                 }
             }
-        }
+        });
     }
 }
 
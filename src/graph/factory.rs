@@ -14,140 +14,458 @@ use sched::{
     tick::TickResched,
     Float,
 };
-use serde_json::value::Value as JsonValue;
-use std::{collections::HashMap, sync::Arc};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, value::Value as JsonValue};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, RwLock},
+};
+
+/// Result from attempt to create a graph item.
+pub type GraphItemResult = Result<GraphItem, CreateError>;
+
+/// Graph Factory Function type.
+pub type GraphItemFn =
+    dyn Fn(&uuid::Uuid, &GraphArgs<'_>, &Arc<dyn QueueSource>) -> GraphItemResult + Send + Sync;
+
+/// The kind of graph item that a factory creates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphItemKind {
+    Root,
+    Node,
+    Leaf,
+}
+
+/// A param slot declared by a graph item type.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphParamDecl {
+    data_type: &'static str,
+    access: &'static str,
+    default: Option<JsonValue>,
+}
+
+/// Creation arguments for a graph item, validated against its declared params.
+///
+/// Arguments are an object with values for any of the declared params, keyed by param name.
+pub struct GraphArgs<'a> {
+    args: Option<&'a serde_json::Map<String, JsonValue>>,
+    params: &'a BTreeMap<&'static str, GraphParamDecl>,
+}
+
+/// Graph Factory Item.
+#[derive(Serialize)] //just for display
+pub struct GraphFactItem {
+    /// Factory function.
+    #[serde(skip_serializing)]
+    func: Box<GraphItemFn>,
+    /// Description
+    desc: String,
+    /// The kind of item created.
+    kind: GraphItemKind,
+    /// Description of any creation arguments accepted in addition to the params.
+    args_schema: Option<JsonValue>,
+    /// The params of the created item, by name.
+    params: BTreeMap<&'static str, GraphParamDecl>,
+}
+
+impl GraphParamDecl {
+    pub fn new(data_type: &'static str, access: &'static str, default: Option<JsonValue>) -> Self {
+        Self {
+            data_type,
+            access,
+            default,
+        }
+    }
+
+    /// Get the data type name.
+    pub fn data_type(&self) -> &'static str {
+        self.data_type
+    }
+
+    /// Get the access name: `"get"` or `"set"`.
+    pub fn access(&self) -> &'static str {
+        self.access
+    }
+
+    /// Get the default value, if there is one.
+    pub fn default(&self) -> Option<&JsonValue> {
+        self.default.as_ref()
+    }
+}
+
+impl GraphFactItem {
+    pub fn new<D>(func: Box<GraphItemFn>, description: D, kind: GraphItemKind) -> Self
+    where
+        D: ToString,
+    {
+        Self {
+            func,
+            desc: description.to_string(),
+            kind,
+            args_schema: None,
+            params: Default::default(),
+        }
+    }
+
+    /// Set the description of any creation arguments accepted in addition to the params.
+    pub fn with_args_schema(mut self, schema: JsonValue) -> Self {
+        self.args_schema = Some(schema);
+        self
+    }
+
+    /// Declare a param slot of the created item.
+    pub fn with_param(mut self, name: &'static str, param: GraphParamDecl) -> Self {
+        self.params.insert(name, param);
+        self
+    }
+
+    pub fn create(
+        &self,
+        uuid: &uuid::Uuid,
+        args: Option<&JsonValue>,
+        queue_sources: &Arc<dyn QueueSource>,
+    ) -> GraphItemResult {
+        let args = GraphArgs::new(args, &self.params)?;
+        (self.func)(uuid, &args, queue_sources)
+    }
+
+    /// Get the description.
+    pub fn description(&self) -> &str {
+        &self.desc
+    }
+
+    /// Get the kind of item created.
+    pub fn kind(&self) -> GraphItemKind {
+        self.kind
+    }
+
+    /// Get the description of any creation arguments accepted in addition to the params.
+    pub fn args_schema(&self) -> Option<&JsonValue> {
+        self.args_schema.as_ref()
+    }
+
+    /// Get the declared params, by name.
+    pub fn params(&self) -> &BTreeMap<&'static str, GraphParamDecl> {
+        &self.params
+    }
+}
+
+impl<'a> GraphArgs<'a> {
+    /// Validate that `args`, if given, is an object whose keys are all declared params.
+    pub fn new(
+        args: Option<&'a JsonValue>,
+        params: &'a BTreeMap<&'static str, GraphParamDecl>,
+    ) -> Result<Self, CreateError> {
+        let args = match args {
+            None | Some(JsonValue::Null) => None,
+            Some(JsonValue::Object(map)) => {
+                if let Some(key) = map.keys().find(|k| !params.contains_key(k.as_str())) {
+                    return Err(CreateError::InvalidArgs(format!("unknown param {}", key)));
+                }
+                Some(map)
+            }
+            Some(v) => {
+                return Err(CreateError::InvalidArgs(format!(
+                    "expected an object, got {}",
+                    v
+                )))
+            }
+        };
+        Ok(Self { args, params })
+    }
+
+    /// Get the value for the param `name` from the args, or its declared default.
+    pub fn get<T: DeserializeOwned>(&self, name: &str) -> Result<T, CreateError> {
+        let value = self
+            .args
+            .and_then(|a| a.get(name))
+            .or_else(|| self.params.get(name).and_then(|p| p.default()))
+            .ok_or_else(|| CreateError::InvalidArgs(format!("no value for param {}", name)))?;
+        serde_json::from_value(value.clone()).map_err(|e| {
+            CreateError::InvalidArgs(format!("invalid value {} for param {}: {}", value, name, e))
+        })
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref GRAPH_FACTORY_HASH: RwLock<HashMap<&'static str, GraphFactItem>> =
+        RwLock::new(builtin());
+}
+
+/// Register a graph item type, returning the registration it replaces, if any.
+///
+/// Registered types can be created with `GraphItemCreate` like the built in types.
+pub fn register(type_name: &'static str, item: GraphFactItem) -> Option<GraphFactItem> {
+    GRAPH_FACTORY_HASH
+        .write()
+        .expect("graph factory poisoned")
+        .insert(type_name, item)
+}
 
 pub fn create_instance(
     uuid: &uuid::Uuid,
     type_name: &str,
-    _args: Option<JsonValue>,
+    args: Option<JsonValue>,
     queue_sources: &Arc<dyn QueueSource>,
-) -> Result<GraphItem, CreateError> {
-    let uuid = uuid.clone();
-    //TODO build.rs BindStoreNode for all the binding types
-    match type_name {
-        "root::clock" => {
-            let clock = ClockData::default();
-            let micros: Arc<BindingSwapGet<Float>> =
-                Arc::new(BindingSwapGet::new(clock.period_micros()));
-            let mut map = HashMap::new();
-            map.insert(
-                "period_micros",
-                ParamAccess::new_get(ParamGet::Float(micros.clone())),
-            );
-            Ok(GraphItem::new_root(
-                &"root::clock",
-                sgraph::root_clock::RootClock::new(
-                    micros.clone() as Arc<dyn ParamBindingGet<Float>>
-                ),
-                map,
-                Some(uuid),
-            ))
-        }
-        "node::clock_ratio" => {
-            let mul: Arc<BindingSwapGet<usize>> = Arc::new(BindingSwapGet::new(1));
-            let div: Arc<BindingSwapGet<usize>> = Arc::new(BindingSwapGet::new(1));
-            let mut map = HashMap::new();
-            map.insert("mul", ParamAccess::new_get(ParamGet::USize(mul.clone())));
-            map.insert("div", ParamAccess::new_get(ParamGet::USize(div.clone())));
-
-            let node = sgraph::clock_ratio::ClockRatio::new(
-                mul as Arc<dyn ParamBindingGet<usize>>,
-                div as Arc<dyn ParamBindingGet<usize>>,
-            );
-            Ok(GraphItem::new_node(
-                &"node::clock_ratio",
-                node,
-                map,
-                Some(uuid),
-            ))
-        }
-        "node::gate" => {
-            let gate: Arc<BindingSwapGet<bool>> = Arc::new(BindingSwapGet::new(false));
-            let mut map = HashMap::new();
-            map.insert("gate", ParamAccess::new_get(ParamGet::Bool(gate.clone())));
+) -> GraphItemResult {
+    let guard = GRAPH_FACTORY_HASH.read().expect("graph factory poisoned");
+    if let Some(f) = guard.get(type_name) {
+        f.create(uuid, args.as_ref(), queue_sources)
+            .map(|item| item.with_args(args))
+    } else {
+        Err(CreateError::TypeNotFound)
+    }
+}
 
-            let node = sgraph::gate::Gate::new(gate as Arc<dyn ParamBindingGet<bool>>);
-            Ok(GraphItem::new_node(&"node::gate", node, map, Some(uuid)))
-        }
-        "node::one_hot" => {
-            let sel: Arc<BindingSwapGet<usize>> = Arc::new(BindingSwapGet::new(0));
-            let mut map = HashMap::new();
-            map.insert("sel", ParamAccess::new_get(ParamGet::USize(sel.clone())));
-            let node = sgraph::one_hot::OneHot::new(sel as Arc<dyn ParamBindingGet<usize>>);
-            Ok(GraphItem::new_node(&"node::one_hot", node, map, Some(uuid)))
-        }
-        "node::fanout" => {
-            let node = sgraph::fanout::FanOut::new();
-            Ok(GraphItem::new_node(
-                &"node::fanout",
-                node,
-                HashMap::new(),
-                Some(uuid),
-            ))
-        }
-        "node::step_seq" => {
-            let step_ticks: Arc<BindingSwapGet<usize>> = Arc::new(BindingSwapGet::new(16));
-            let steps: Arc<BindingSwapGet<usize>> = Arc::new(BindingSwapGet::new(16));
-            let mut map = HashMap::new();
-            map.insert(
-                "step_ticks",
-                ParamAccess::new_get(ParamGet::USize(step_ticks.clone())),
-            );
-            map.insert(
-                "steps",
-                ParamAccess::new_get(ParamGet::USize(steps.clone())),
-            );
-
-            let node = sgraph::step_seq::StepSeq::new(
-                step_ticks as Arc<dyn ParamBindingGet<usize>>,
-                steps as Arc<dyn ParamBindingGet<usize>>,
-            );
-            Ok(GraphItem::new_node(
-                &"node::step_seq",
-                node,
-                map,
-                Some(uuid),
-            ))
-        }
-        "leaf::midi::note" => {
-            let chan: Arc<BindingSwapGet<u8>> = Arc::new(BindingSwapGet::new(0));
-            let num: Arc<BindingSwapGet<u8>> = Arc::new(BindingSwapGet::new(64));
-            let on_vel: Arc<BindingSwapGet<u8>> = Arc::new(BindingSwapGet::new(127));
-            let off_vel: Arc<BindingSwapGet<u8>> = Arc::new(BindingSwapGet::new(127));
-            let dur: Arc<BindingSwapGet<TickResched>> =
-                Arc::new(BindingSwapGet::new(TickResched::ContextRelative(1)));
-
-            //setup parameters
-            let mut map = HashMap::new();
-            map.insert("chan", ParamAccess::new_get(ParamGet::U8(chan.clone())));
-            map.insert("num", ParamAccess::new_get(ParamGet::U8(num.clone())));
-            map.insert("on_vel", ParamAccess::new_get(ParamGet::U8(on_vel.clone())));
-            map.insert(
-                "off_vel",
-                ParamAccess::new_get(ParamGet::U8(off_vel.clone())),
-            );
-            map.insert(
-                "dur",
-                ParamAccess::new_get(ParamGet::TickResched(dur.clone())),
-            );
-
-            let note = sgraph::midi::MidiNote::new(
-                chan as Arc<dyn ParamBindingGet<u8>>,
-                num as Arc<dyn ParamBindingGet<u8>>,
-                dur as Arc<dyn ParamBindingGet<TickResched>>,
-                on_vel as Arc<dyn ParamBindingGet<u8>>,
-                off_vel as Arc<dyn ParamBindingGet<u8>>,
-                queue_sources.midi_event_source(),
-                queue_sources.midi_queue() as _,
-            );
-            Ok(GraphItem::new_leaf(
-                &"leaf::midi::note",
-                note,
-                map,
-                Some(uuid),
-            ))
-        }
-        _ => Err(CreateError::TypeNotFound),
+/// Describe every graph item type: its description, kind, args and params.
+pub fn help() -> JsonValue {
+    let guard = GRAPH_FACTORY_HASH.read().expect("graph factory poisoned");
+    serde_json::to_value(&*guard).expect("failed to serialize")
+}
+
+fn get_decl(data_type: &'static str, default: JsonValue) -> GraphParamDecl {
+    GraphParamDecl::new(data_type, "get", Some(default))
+}
+
+//TODO build.rs BindStoreNode for all the binding types
+fn builtin() -> HashMap<&'static str, GraphFactItem> {
+    let mut m = HashMap::new();
+    m.insert(
+        "root::clock",
+        GraphFactItem::new(
+            Box::new(|uuid, args, _queue_sources| {
+                let micros: Arc<BindingSwapGet<Float>> =
+                    Arc::new(BindingSwapGet::new(args.get("period_micros")?));
+                let mut map = HashMap::new();
+                map.insert(
+                    "period_micros",
+                    ParamAccess::new_get(ParamGet::Float(micros.clone())),
+                );
+                Ok(GraphItem::new_root(
+                    &"root::clock",
+                    sgraph::root_clock::RootClock::new(
+                        micros.clone() as Arc<dyn ParamBindingGet<Float>>
+                    ),
+                    map,
+                    Some(uuid.clone()),
+                ))
+            }),
+            "root that evaluates its children every tick, at the period given by period_micros",
+            GraphItemKind::Root,
+        )
+        .with_param(
+            "period_micros",
+            get_decl("Float", json!(ClockData::default().period_micros())),
+        ),
+    );
+    m.insert(
+        "node::clock_ratio",
+        GraphFactItem::new(
+            Box::new(|uuid, args, _queue_sources| {
+                let mul: Arc<BindingSwapGet<usize>> =
+                    Arc::new(BindingSwapGet::new(args.get("mul")?));
+                let div: Arc<BindingSwapGet<usize>> =
+                    Arc::new(BindingSwapGet::new(args.get("div")?));
+                let mut map = HashMap::new();
+                map.insert("mul", ParamAccess::new_get(ParamGet::USize(mul.clone())));
+                map.insert("div", ParamAccess::new_get(ParamGet::USize(div.clone())));
+
+                let node = sgraph::clock_ratio::ClockRatio::new(
+                    mul as Arc<dyn ParamBindingGet<usize>>,
+                    div as Arc<dyn ParamBindingGet<usize>>,
+                );
+                Ok(GraphItem::new_node(
+                    &"node::clock_ratio",
+                    node,
+                    map,
+                    Some(uuid.clone()),
+                ))
+            }),
+            "evaluates its children at mul/div times the rate of its parent",
+            GraphItemKind::Node,
+        )
+        .with_param("mul", get_decl("usize", json!(1)))
+        .with_param("div", get_decl("usize", json!(1))),
+    );
+    m.insert(
+        "node::gate",
+        GraphFactItem::new(
+            Box::new(|uuid, args, _queue_sources| {
+                let gate: Arc<BindingSwapGet<bool>> =
+                    Arc::new(BindingSwapGet::new(args.get("gate")?));
+                let mut map = HashMap::new();
+                map.insert("gate", ParamAccess::new_get(ParamGet::Bool(gate.clone())));
+
+                let node = sgraph::gate::Gate::new(gate as Arc<dyn ParamBindingGet<bool>>);
+                Ok(GraphItem::new_node(
+                    &"node::gate",
+                    node,
+                    map,
+                    Some(uuid.clone()),
+                ))
+            }),
+            "evaluates its children only when gate is true",
+            GraphItemKind::Node,
+        )
+        .with_param("gate", get_decl("bool", json!(false))),
+    );
+    m.insert(
+        "node::one_hot",
+        GraphFactItem::new(
+            Box::new(|uuid, args, _queue_sources| {
+                let sel: Arc<BindingSwapGet<usize>> =
+                    Arc::new(BindingSwapGet::new(args.get("sel")?));
+                let mut map = HashMap::new();
+                map.insert("sel", ParamAccess::new_get(ParamGet::USize(sel.clone())));
+                let node = sgraph::one_hot::OneHot::new(sel as Arc<dyn ParamBindingGet<usize>>);
+                Ok(GraphItem::new_node(
+                    &"node::one_hot",
+                    node,
+                    map,
+                    Some(uuid.clone()),
+                ))
+            }),
+            "evaluates only the child at index sel",
+            GraphItemKind::Node,
+        )
+        .with_param("sel", get_decl("usize", json!(0))),
+    );
+    m.insert(
+        "node::fanout",
+        GraphFactItem::new(
+            Box::new(|uuid, _args, _queue_sources| {
+                let node = sgraph::fanout::FanOut::new();
+                Ok(GraphItem::new_node(
+                    &"node::fanout",
+                    node,
+                    HashMap::new(),
+                    Some(uuid.clone()),
+                ))
+            }),
+            "evaluates all of its children",
+            GraphItemKind::Node,
+        ),
+    );
+    m.insert(
+        "node::step_seq",
+        GraphFactItem::new(
+            Box::new(|uuid, args, _queue_sources| {
+                let step_ticks: Arc<BindingSwapGet<usize>> =
+                    Arc::new(BindingSwapGet::new(args.get("step_ticks")?));
+                let steps: Arc<BindingSwapGet<usize>> =
+                    Arc::new(BindingSwapGet::new(args.get("steps")?));
+                let mut map = HashMap::new();
+                map.insert(
+                    "step_ticks",
+                    ParamAccess::new_get(ParamGet::USize(step_ticks.clone())),
+                );
+                map.insert(
+                    "steps",
+                    ParamAccess::new_get(ParamGet::USize(steps.clone())),
+                );
+
+                let node = sgraph::step_seq::StepSeq::new(
+                    step_ticks as Arc<dyn ParamBindingGet<usize>>,
+                    steps as Arc<dyn ParamBindingGet<usize>>,
+                );
+                Ok(GraphItem::new_node(
+                    &"node::step_seq",
+                    node,
+                    map,
+                    Some(uuid.clone()),
+                ))
+            }),
+            "evaluates its children once per step, every step_ticks, for steps steps",
+            GraphItemKind::Node,
+        )
+        .with_param("step_ticks", get_decl("usize", json!(16)))
+        .with_param("steps", get_decl("usize", json!(16))),
+    );
+    m.insert(
+        "leaf::midi::note",
+        GraphFactItem::new(
+            Box::new(|uuid, args, queue_sources| {
+                let chan: Arc<BindingSwapGet<u8>> =
+                    Arc::new(BindingSwapGet::new(args.get("chan")?));
+                let num: Arc<BindingSwapGet<u8>> = Arc::new(BindingSwapGet::new(args.get("num")?));
+                let on_vel: Arc<BindingSwapGet<u8>> =
+                    Arc::new(BindingSwapGet::new(args.get("on_vel")?));
+                let off_vel: Arc<BindingSwapGet<u8>> =
+                    Arc::new(BindingSwapGet::new(args.get("off_vel")?));
+                let dur: Arc<BindingSwapGet<TickResched>> =
+                    Arc::new(BindingSwapGet::new(args.get("dur")?));
+
+                //setup parameters
+                let mut map = HashMap::new();
+                map.insert("chan", ParamAccess::new_get(ParamGet::U8(chan.clone())));
+                map.insert("num", ParamAccess::new_get(ParamGet::U8(num.clone())));
+                map.insert("on_vel", ParamAccess::new_get(ParamGet::U8(on_vel.clone())));
+                map.insert(
+                    "off_vel",
+                    ParamAccess::new_get(ParamGet::U8(off_vel.clone())),
+                );
+                map.insert(
+                    "dur",
+                    ParamAccess::new_get(ParamGet::TickResched(dur.clone())),
+                );
+
+                let note = sgraph::midi::MidiNote::new(
+                    chan as Arc<dyn ParamBindingGet<u8>>,
+                    num as Arc<dyn ParamBindingGet<u8>>,
+                    dur as Arc<dyn ParamBindingGet<TickResched>>,
+                    on_vel as Arc<dyn ParamBindingGet<u8>>,
+                    off_vel as Arc<dyn ParamBindingGet<u8>>,
+                    queue_sources.midi_event_source(),
+                    queue_sources.midi_queue() as _,
+                );
+                Ok(GraphItem::new_leaf(
+                    &"leaf::midi::note",
+                    note,
+                    map,
+                    Some(uuid.clone()),
+                ))
+            }),
+            "schedules a midi note on and, after dur, a note off",
+            GraphItemKind::Leaf,
+        )
+        .with_param("chan", get_decl("u8", json!(0)))
+        .with_param("num", get_decl("u8", json!(64)))
+        .with_param("on_vel", get_decl("u8", json!(127)))
+        .with_param("off_vel", get_decl("u8", json!(127)))
+        .with_param(
+            "dur",
+            get_decl("TickResched", json!(TickResched::ContextRelative(1))),
+        ),
+    );
+    m
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn args() {
+        let mut params = BTreeMap::new();
+        params.insert("num", get_decl("u8", json!(64)));
+        params.insert("chan", get_decl("u8", json!(0)));
+
+        let a = GraphArgs::new(None, &params).unwrap();
+        assert_eq!(64u8, a.get("num").unwrap());
+
+        let v = json!({"num": 60});
+        let a = GraphArgs::new(Some(&v), &params).unwrap();
+        assert_eq!(60u8, a.get("num").unwrap());
+        assert_eq!(0u8, a.get("chan").unwrap());
+
+        let v = json!({"num": 300});
+        let a = GraphArgs::new(Some(&v), &params).unwrap();
+        assert!(a.get::<u8>("num").is_err());
+
+        let v = json!({"note": 60});
+        assert!(GraphArgs::new(Some(&v), &params).is_err());
+        let v = json!(60);
+        assert!(GraphArgs::new(Some(&v), &params).is_err());
     }
 }
@@ -1,3 +1,5 @@
+pub mod change;
+pub mod cycle;
 pub mod error;
 pub mod graph;
 pub mod jack;
@@ -27,6 +27,40 @@ fn main() -> Result<(), std::io::Error> {
     let _jack = Jack::new(sched);
     let mut server = OSCQueryHandler::new(queue_sources, bindings, graph)?;
 
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--session" => {
+                if let Some(path) = args.next() {
+                    if let Err(e) = server.session_load(&path) {
+                        eprintln!("failed to load session {}: {}", path, e);
+                    }
+                } else {
+                    eprintln!("--session requires a path");
+                }
+            }
+            "--journal" => {
+                if let Some(path) = args.next() {
+                    if let Err(e) = server.journal_record(&path) {
+                        eprintln!("failed to open journal {}: {}", path, e);
+                    }
+                } else {
+                    eprintln!("--journal requires a path");
+                }
+            }
+            "--replay" => {
+                if let Some(path) = args.next() {
+                    if let Err(e) = server.journal_replay(&path) {
+                        eprintln!("failed to read journal {}: {}", path, e);
+                    }
+                } else {
+                    eprintln!("--replay requires a path");
+                }
+            }
+            _ => eprintln!("unknown argument {}", arg),
+        }
+    }
+
     /*
     server.add_binding(Arc::new(Param::new(
         &"value",
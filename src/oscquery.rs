@@ -1,26 +1,32 @@
 use crate::{
-    graph::{children::Children, GraphItem},
-    param::{Param, ParamDataAccess, ParamMapGet},
-    sched::{EventQueue, QueueSource},
+    change::{Change, ChangeEvent},
+    cycle::creates_cycle,
+    error::CommandError,
+    graph::{children::Children, ChildrenUUIDs, GraphItem},
+    param::{BindingError, Param, ParamDataAccess, ParamHashMap, ParamMapGet},
+    sched::{DisposeSender, EventQueue, QueueSource},
 };
 use oscquery::{
     func_wrap::{GetFunc, GetSetFuncs, OscUpdateFunc, SetFunc},
-    osc::{OscArray, OscType},
+    osc::{OscArray, OscMessage, OscPacket, OscType},
     param::{ParamGet, ParamGetSet, ParamSet},
     root::{NodeHandle, OscWriteCallback},
     value::{ClipMode, Range, ValueBuilder},
     OscQueryServer,
 };
 use sched::{
+    atomic::{Atomic, Ordering},
     binding::bpm::{Clock, ClockData},
-    graph::GraphNodeContainer,
+    event::EventContainer,
     pqueue::TickPriorityEnqueue,
     tick::TickResched,
 };
 
 use std::{
-    collections::HashMap,
-    net::SocketAddr,
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::BufWriter,
+    net::{SocketAddr, UdpSocket},
     str::FromStr,
     sync::{
         mpsc::{Receiver, SyncSender},
@@ -31,47 +37,167 @@ use std::{
 use serde::{Deserialize, Serialize};
 use serde_json::value::Value as JsonValue;
 
+mod atomic;
+mod dump;
+mod journal;
+mod pending;
+mod session;
+
+/// A uuid, or an alias for one.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum IdOrAlias {
+    Id(uuid::Uuid),
+    Alias(String),
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
-enum ParamOwner {
-    Param(uuid::Uuid),
-    GraphItem(uuid::Uuid),
+enum ParamOwner<I = uuid::Uuid> {
+    Param(I),
+    GraphItem(I),
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
-enum GraphNodeChildren {
+enum GraphNodeChildren<I = uuid::Uuid> {
     None,
-    NChild(uuid::Uuid),
-    Indexed(Vec<uuid::Uuid>),
+    NChild(I),
+    Indexed(Vec<I>),
 }
 
+/// The result of handling a command.
+pub type CommandResult = Result<(), CommandError>;
+
+type CommandMessage = (Result<CommandRequest, CommandError>, Option<SocketAddr>);
+
+/// A command and an optional id that is sent back along with its result.
 #[derive(Deserialize, Serialize)]
+struct CommandRequest {
+    request_id: Option<String>,
+    #[serde(flatten)]
+    command: Command,
+}
+
+/// The result of handling a command, along with any output that the command produces.
+type CommandOutput = Result<Option<JsonValue>, CommandError>;
+
+#[derive(Serialize)]
+struct CommandResponse<'a> {
+    request_id: &'a Option<String>,
+    result: &'a CommandOutput,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 enum Command {
     Batch(Box<Vec<Command>>),
+    /// Apply all of the commands or, if any of them fail, none of them.
+    ///
+    /// Every command is validated, and new items are created and bound, before anything is
+    /// applied. Later commands may refer to items, and aliases, created earlier in the batch.
+    /// Changes to existing items are applied, and roots created in the batch are started, together
+    /// at the next tick in the scheduler thread.
+    /// Only creates, binds, unbinds, setting children and alias changes are allowed.
+    AtomicBatch(Box<Vec<Command>>),
     ParamBind {
-        owner: ParamOwner,
+        owner: ParamOwner<IdOrAlias>,
         param_name: String,
-        param_id: uuid::Uuid,
+        param_id: IdOrAlias,
     },
     ParamUnbind {
-        owner: ParamOwner,
+        owner: ParamOwner<IdOrAlias>,
         param_name: String,
     },
     ParamCreate {
         id: Option<uuid::Uuid>,
         type_name: String,
         args: JsonValue,
-        params: Option<HashMap<String, uuid::Uuid>>,
+        params: Option<HashMap<String, IdOrAlias>>,
     },
+    /// Create a graph item, roots are started unless `autostart` is `false`.
     GraphItemCreate {
         id: Option<uuid::Uuid>,
         type_name: String,
         args: Option<JsonValue>,
-        children: Option<GraphNodeChildren>,
-        params: Option<HashMap<String, uuid::Uuid>>,
+        children: Option<GraphNodeChildren<IdOrAlias>>,
+        params: Option<HashMap<String, IdOrAlias>>,
+        autostart: Option<bool>,
     },
     GraphNodeSetChildren {
-        parent_id: uuid::Uuid,
-        children: GraphNodeChildren,
+        parent_id: IdOrAlias,
+        children: GraphNodeChildren<IdOrAlias>,
+    },
+    ParamDelete {
+        id: IdOrAlias,
+    },
+    GraphItemDelete {
+        id: IdOrAlias,
+    },
+    AliasSet {
+        name: String,
+        id: IdOrAlias,
+    },
+    AliasRemove {
+        name: String,
+    },
+    /// Start a root, if it isn't already active.
+    RootStart {
+        id: IdOrAlias,
+        at: Option<ScheduleAt>,
+    },
+    /// Stop a root.
+    RootStop {
+        id: IdOrAlias,
+        at: Option<ScheduleAt>,
+    },
+    /// Start a root from the beginning, halting any current processing.
+    RootRestart {
+        id: IdOrAlias,
+        at: Option<ScheduleAt>,
+    },
+    /// Undo the most recent creates, binds, unbinds, set children and alias changes.
+    ///
+    /// Deletes and session loads cannot be undone and clear the history.
+    Undo,
+    /// Reapply the most recently undone changes.
+    Redo,
+    /// Describe all the params, graph items, children and bindings, json by default.
+    ///
+    /// The description is returned in the command result, dot is returned as a json string.
+    Dump {
+        format: Option<dump::DumpFormat>,
+    },
+    /// Save all the params, graph items and aliases to a json file.
+    SessionSave {
+        path: String,
+    },
+    /// Load params, graph items and aliases from a json file, all or nothing.
+    SessionLoad {
+        path: String,
+    },
+    /// Apply the structural changes in `command` at a specific tick.
+    ///
+    /// Only binds, unbinds, setting children, starting and stopping roots and batches of those
+    /// can be scheduled.
+    /// The changes are validated when the command is received, along with the other changes that
+    /// are still pending, and applied together in the scheduler thread.
+    /// Items used by a pending change cannot be deleted until it has been applied.
+    Scheduled {
+        at: ScheduleAt,
+        command: Box<Command>,
+    },
+}
+
+/// When to apply a scheduled command.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+enum ScheduleAt {
+    /// At the given absolute tick, as soon as possible if it has already passed.
+    Tick(usize),
+    /// After the given number of ticks.
+    Delay(usize),
+    /// At the next multiple of `ticks` since the given root was started,
+    /// or since tick 0 if there is no root.
+    Quantized {
+        ticks: usize,
+        root: Option<IdOrAlias>,
     },
 }
 
@@ -90,17 +216,204 @@ struct GraphChildrenTypeNameParamGet {
     owner: Weak<GraphItem>,
 }
 
+//a record of the changes made by applying commands
+#[derive(Default)]
+struct ApplyLog {
+    //commands that undo the applied changes, in the order they were logged
+    undo: Vec<Command>,
+    //the applied changes with ids resolved, in the order they were logged, to redo them
+    done: Vec<Command>,
+    //changes were made that cannot be undone
+    irreversible: bool,
+    //output to return with the result
+    output: Option<JsonValue>,
+    //graph items to start, if they are roots, after all the commands have been applied
+    roots: Vec<uuid::Uuid>,
+}
+
+//changes to be applied later in the scheduler thread
+#[derive(Default)]
+struct Prepared {
+    changes: Vec<Change>,
+    //root events to schedule along with the changes, and their gates
+    events: Vec<(EventContainer, Arc<Atomic<bool>>)>,
+    //roots that will be started or stopped by the changes
+    started: Vec<uuid::Uuid>,
+    stopped: Vec<uuid::Uuid>,
+    //dependencies the changes will add, so later changes in the same batch are checked with them
+    edges: pending::Edges,
+}
+
+//the most recent command error, as a json string
+#[derive(Default)]
+struct LastError(std::sync::Mutex<String>);
+
+//the maximum number of commands that can be undone
+const HISTORY_DEPTH: usize = 256;
+
+//the changes made by a command and the commands that undo them
+struct HistoryEntry {
+    done: Vec<Command>,
+    undo: Vec<Command>,
+}
+
+//a stack of commands that can be undone or redone
+#[derive(Default)]
+struct History(std::sync::Mutex<Vec<HistoryEntry>>);
+
 pub struct OSCQueryHandler {
-    params: std::sync::Mutex<HashMap<uuid::Uuid, Arc<Param>>>,
-    graph: std::sync::Mutex<HashMap<uuid::Uuid, Arc<GraphItem>>>,
-    command_sender: SyncSender<Command>,
+    params: dump::ParamMap,
+    graph: dump::GraphMap,
+    handles: std::sync::Mutex<HashMap<uuid::Uuid, NodeHandle>>,
+    aliases: std::sync::Mutex<HashMap<String, (uuid::Uuid, NodeHandle)>>,
+    root_starts: std::sync::Mutex<HashMap<uuid::Uuid, usize>>,
+    pending: std::sync::Mutex<pending::Pending>,
+    command_sender: SyncSender<CommandMessage>,
     server: OscQueryServer,
     _xsched_handle: NodeHandle,
     params_handle: NodeHandle,
     graph_handle: NodeHandle,
-    command_receiver: Receiver<Command>,
+    aliases_handle: NodeHandle,
+    last_error: Arc<LastError>,
+    last_error_handle: NodeHandle,
+    undo_history: Arc<History>,
+    undo_depth_handle: NodeHandle,
+    redo_history: Arc<History>,
+    redo_depth_handle: NodeHandle,
+    reply_socket: UdpSocket,
+    command_receiver: Receiver<CommandMessage>,
     sched_queue: EventQueue,
+    dispose_sender: DisposeSender,
     queue_sources: Arc<dyn QueueSource>,
+    journal: Option<BufWriter<File>>,
+    replay: VecDeque<journal::JournalEntry>,
+}
+
+impl ParamOwner {
+    fn uuid(&self) -> uuid::Uuid {
+        match self {
+            Self::Param(id) | Self::GraphItem(id) => id.clone(),
+        }
+    }
+}
+
+impl From<uuid::Uuid> for IdOrAlias {
+    fn from(id: uuid::Uuid) -> Self {
+        Self::Id(id)
+    }
+}
+
+impl From<&ParamOwner> for ParamOwner<IdOrAlias> {
+    fn from(owner: &ParamOwner) -> Self {
+        match owner {
+            ParamOwner::Param(id) => Self::Param(id.clone().into()),
+            ParamOwner::GraphItem(id) => Self::GraphItem(id.clone().into()),
+        }
+    }
+}
+
+impl From<GraphNodeChildren> for GraphNodeChildren<IdOrAlias> {
+    fn from(children: GraphNodeChildren) -> Self {
+        match children {
+            GraphNodeChildren::None => Self::None,
+            GraphNodeChildren::NChild(id) => Self::NChild(id.into()),
+            GraphNodeChildren::Indexed(ids) => {
+                Self::Indexed(ids.into_iter().map(|id| id.into()).collect())
+            }
+        }
+    }
+}
+
+impl Command {
+    //give every create without an id a new one, so the command can be journaled and replayed
+    //with the same ids
+    fn with_ids(self) -> Self {
+        match self {
+            Self::Batch(b) => Self::Batch(Box::new(b.into_iter().map(Self::with_ids).collect())),
+            Self::AtomicBatch(b) => {
+                Self::AtomicBatch(Box::new(b.into_iter().map(Self::with_ids).collect()))
+            }
+            Self::ParamCreate {
+                id,
+                type_name,
+                args,
+                params,
+            } => Self::ParamCreate {
+                id: Some(id.unwrap_or_else(|| uuid::Uuid::new_v4())),
+                type_name,
+                args,
+                params,
+            },
+            Self::GraphItemCreate {
+                id,
+                type_name,
+                args,
+                children,
+                params,
+                autostart,
+            } => Self::GraphItemCreate {
+                id: Some(id.unwrap_or_else(|| uuid::Uuid::new_v4())),
+                type_name,
+                args,
+                children,
+                params,
+                autostart,
+            },
+            Self::Scheduled { at, command } => Self::Scheduled {
+                at,
+                command: Box::new(command.with_ids()),
+            },
+            cmd => cmd,
+        }
+    }
+}
+
+impl From<&ChildrenUUIDs> for GraphNodeChildren {
+    fn from(children: &ChildrenUUIDs) -> Self {
+        match children.0.as_ref() {
+            Children::None => Self::None,
+            Children::NChild { .. } => Self::NChild(children.1[0].clone()),
+            Children::Indexed { .. } => Self::Indexed(children.1.clone()),
+        }
+    }
+}
+
+//convert resolved param bindings back to a command argument
+fn resolved(params: &Option<HashMap<String, uuid::Uuid>>) -> Option<HashMap<String, IdOrAlias>> {
+    params.as_ref().map(|p| {
+        p.iter()
+            .map(|(k, v)| (k.clone(), v.clone().into()))
+            .collect()
+    })
+}
+
+//describe the children of a graph item, if it can have any
+fn graph_node_children(item: &GraphItem) -> Option<GraphNodeChildren> {
+    let uuids = item.children_uuids()?;
+    Some(match item.children_type_name() {
+        Some("NChild") => uuids
+            .first()
+            .cloned()
+            .map_or(GraphNodeChildren::None, GraphNodeChildren::NChild),
+        Some("Indexed") => GraphNodeChildren::Indexed(uuids),
+        _ => GraphNodeChildren::None,
+    })
+}
+
+//the command that restores the binding of `param_name` to `param_id`, or unbinds it
+fn rebind(owner: &ParamOwner, param_name: &str, param_id: Option<uuid::Uuid>) -> Command {
+    if let Some(param_id) = param_id {
+        Command::ParamBind {
+            owner: owner.into(),
+            param_name: param_name.to_string(),
+            param_id: param_id.into(),
+        }
+    } else {
+        Command::ParamUnbind {
+            owner: owner.into(),
+            param_name: param_name.to_string(),
+        }
+    }
 }
 
 impl ParamOSCQueryGet {
@@ -112,10 +425,62 @@ impl ParamOSCQueryGet {
     }
 }
 
+//the values of an osc list, sent either as the args or as a single array arg
+fn osc_list(args: &[OscType]) -> &[OscType] {
+    match args {
+        [OscType::Array(a)] => &a.content,
+        _ => args,
+    }
+}
+
+fn io_error(e: std::io::Error) -> CommandError {
+    CommandError::Io(e.to_string())
+}
+
+/// Can `name` be used as a part of an OSC address: not empty and without any of `/ #*,?[]{}`.
+pub fn valid_address_part(name: &str) -> bool {
+    !name.is_empty() && !name.contains(|c| "/ #*,?[]{}".contains(c))
+}
+
 fn map_uuid(uuid: &uuid::Uuid) -> String {
     uuid.to_hyphenated().to_string()
 }
 
+//validate an alias name and build the node that publishes the id it refers to
+fn alias_node(name: &str, id: &uuid::Uuid) -> Result<oscquery::node::Get, CommandError> {
+    //an alias that parses as a uuid would be ambiguous
+    if !valid_address_part(name) || uuid::Uuid::parse_str(name).is_ok() {
+        return Err(CommandError::InvalidAlias(name.to_string()));
+    }
+    let value = map_uuid(id);
+    oscquery::node::Get::new(
+        name.to_string(),
+        Some("uuid"),
+        vec![ParamGet::String(
+            ValueBuilder::new(Arc::new(GetFunc::new(move || value.clone())) as _).build(),
+        )],
+    )
+    .map_err(|_| CommandError::InvalidAlias(name.to_string()))
+}
+
+//unbind any parameters in the map that are bound to the given id
+fn unbind_matching(map: &ParamHashMap, id: &uuid::Uuid) {
+    let keys: Vec<_> = map.keys().cloned().collect();
+    for key in keys {
+        if map.uuid(key).as_ref() == Some(id) {
+            map.unbind(key);
+        }
+    }
+}
+
+//unbind all the parameters in the map
+fn unbind_all(map: &ParamHashMap) {
+    let keys: Vec<_> = map.keys().cloned().collect();
+    for key in keys {
+        map.unbind(key);
+    }
+}
+
 impl ::oscquery::value::Get<String> for ParamOSCQueryGet {
     fn get(&self) -> String {
         self.map.upgrade().map_or("".into(), |m| {
@@ -126,6 +491,41 @@ impl ::oscquery::value::Get<String> for ParamOSCQueryGet {
     }
 }
 
+impl ::oscquery::value::Get<String> for LastError {
+    fn get(&self) -> String {
+        self.0.lock().map_or("".into(), |e| e.clone())
+    }
+}
+
+impl ::oscquery::value::Get<i32> for History {
+    fn get(&self) -> i32 {
+        self.0.lock().map_or(0, |h| h.len() as i32)
+    }
+}
+
+impl History {
+    fn push(&self, entry: HistoryEntry) {
+        let mut h = self.0.lock().unwrap();
+        if h.len() >= HISTORY_DEPTH {
+            h.remove(0);
+        }
+        h.push(entry);
+    }
+
+    fn pop(&self) -> Option<HistoryEntry> {
+        self.0.lock().unwrap().pop()
+    }
+
+    //apply `f` to the most recent entry, if there is one
+    fn with_last<R>(&self, f: impl FnOnce(&HistoryEntry) -> R) -> Option<R> {
+        self.0.lock().unwrap().last().map(f)
+    }
+
+    fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
 impl ::oscquery::value::Get<OscArray> for GraphChildrenParamGet {
     fn get(&self) -> OscArray {
         let mut children = Vec::new();
@@ -160,9 +560,9 @@ impl OSCQueryHandler {
         println!(
             "example command {}",
             serde_json::to_string(&Command::ParamBind {
-                owner: ParamOwner::Param(uuid::Uuid::new_v4()),
+                owner: ParamOwner::Param(IdOrAlias::Id(uuid::Uuid::new_v4())),
                 param_name: "toast".into(),
-                param_id: uuid::Uuid::new_v4(),
+                param_id: IdOrAlias::Alias("bpm".into()),
             })
             .unwrap()
         );
@@ -195,24 +595,18 @@ impl OSCQueryHandler {
                         )],
                         Some(Box::new(OscUpdateFunc::new(
                             move |args: &Vec<OscType>,
-                                  _addr: Option<SocketAddr>,
+                                  addr: Option<SocketAddr>,
                                   _time: Option<(u32, u32)>,
                                   _handle: &NodeHandle|
                                   -> Option<OscWriteCallback> {
                                 match args.first() {
                                     Some(OscType::String(v)) => {
                                         println!("got command {}", v);
-                                        let cmd: Result<Command, _> =
-                                            serde_json::from_str(v.as_str());
-                                        if let Ok(cmd) = cmd {
-                                            if command_sender.send(cmd).is_err() {
-                                                eprintln!("error sending command");
-                                            }
-                                        } else {
-                                            eprintln!(
-                                                "failed to deserialize command from str {}",
-                                                v
-                                            );
+                                        let cmd: Result<CommandRequest, _> =
+                                            serde_json::from_str(v.as_str())
+                                                .map_err(|e| CommandError::Parse(e.to_string()));
+                                        if command_sender.send((cmd, addr)).is_err() {
+                                            eprintln!("error sending command");
                                         }
                                     }
                                     _ => (),
@@ -227,6 +621,55 @@ impl OSCQueryHandler {
                 .unwrap();
         }
 
+        let status_handle = server
+            .add_node(
+                oscquery::node::Container::new("status", Some("xsched scheduler status")).unwrap(),
+                Some(xsched_handle),
+            )
+            .unwrap();
+        let last_error: Arc<LastError> = Default::default();
+        let last_error_handle = server
+            .add_node(
+                oscquery::node::Get::new(
+                    "last_error",
+                    Some("json formatted result of the last command that failed"),
+                    vec![ParamGet::String(
+                        ValueBuilder::new(last_error.clone() as _).build(),
+                    )],
+                )
+                .unwrap(),
+                Some(status_handle),
+            )
+            .unwrap();
+        let undo_history: Arc<History> = Default::default();
+        let undo_depth_handle = server
+            .add_node(
+                oscquery::node::Get::new(
+                    "undo_depth",
+                    Some("the number of commands that can be undone"),
+                    vec![ParamGet::Int(
+                        ValueBuilder::new(undo_history.clone() as _).build(),
+                    )],
+                )
+                .unwrap(),
+                Some(status_handle),
+            )
+            .unwrap();
+        let redo_history: Arc<History> = Default::default();
+        let redo_depth_handle = server
+            .add_node(
+                oscquery::node::Get::new(
+                    "redo_depth",
+                    Some("the number of undone commands that can be redone"),
+                    vec![ParamGet::Int(
+                        ValueBuilder::new(redo_history.clone() as _).build(),
+                    )],
+                )
+                .unwrap(),
+                Some(status_handle),
+            )
+            .unwrap();
+
         let params_base = server
             .add_node(
                 oscquery::node::Container::new("params", Some("xsched scheduler params")).unwrap(),
@@ -239,8 +682,32 @@ impl OSCQueryHandler {
                 Some(params_base),
             )
             .unwrap();
+        {
+            let _ = server
+                .add_node(
+                    oscquery::node::Get::new(
+                        "available",
+                        Some("json description of the param types that can be created"),
+                        vec![ParamGet::String(
+                            ValueBuilder::new(Arc::new(GetFunc::new(|| {
+                                crate::param::factory::help().to_string()
+                            })) as _)
+                            .build(),
+                        )],
+                    )
+                    .unwrap(),
+                    Some(params_base),
+                )
+                .unwrap();
+        }
+
+        let aliases_handle = server
+            .add_node(
+                oscquery::node::Container::new("aliases", Some("uuids by alias")).unwrap(),
+                Some(xsched_handle),
+            )
+            .unwrap();
 
-        //TODO aliases
         let graph_base = server
             .add_node(
                 oscquery::node::Container::new("graph", Some("xsched scheduler graph")).unwrap(),
@@ -256,17 +723,83 @@ impl OSCQueryHandler {
             )
             .unwrap();
 
+        {
+            let _ = server
+                .add_node(
+                    oscquery::node::Get::new(
+                        "available",
+                        Some("json description of the graph item types that can be created"),
+                        vec![ParamGet::String(
+                            ValueBuilder::new(Arc::new(GetFunc::new(|| {
+                                crate::graph::factory::help().to_string()
+                            })) as _)
+                            .build(),
+                        )],
+                    )
+                    .unwrap(),
+                    Some(graph_base),
+                )
+                .unwrap();
+        }
+
+        let params: dump::ParamMap = Default::default();
+        let graph: dump::GraphMap = Default::default();
+        for (name, desc, format) in &[
+            (
+                "dump",
+                "json description of all the params, graph items and bindings",
+                dump::DumpFormat::Json,
+            ),
+            (
+                "dot",
+                "graphviz dot rendering of all the params, graph items and bindings",
+                dump::DumpFormat::Dot,
+            ),
+        ] {
+            let _ = server
+                .add_node(
+                    oscquery::node::Get::new(
+                        *name,
+                        Some(*desc),
+                        vec![ParamGet::String(
+                            ValueBuilder::new(
+                                Arc::new(dump::Dump::new(&params, &graph, *format)) as _
+                            )
+                            .build(),
+                        )],
+                    )
+                    .unwrap(),
+                    Some(graph_base),
+                )
+                .unwrap();
+        }
+
         let s = Self {
             server,
             _xsched_handle: xsched_handle,
             params_handle,
             graph_handle,
-            params: Default::default(),
-            graph: Default::default(),
+            aliases_handle,
+            last_error,
+            last_error_handle,
+            undo_history,
+            undo_depth_handle,
+            redo_history,
+            redo_depth_handle,
+            reply_socket: UdpSocket::bind("0.0.0.0:0")?,
+            params,
+            graph,
+            handles: Default::default(),
+            aliases: Default::default(),
+            root_starts: Default::default(),
+            pending: Default::default(),
             command_sender,
             command_receiver,
             sched_queue: queue_sources.sched_queue(),
+            dispose_sender: queue_sources.dispose_sender(),
             queue_sources,
+            journal: None,
+            replay: Default::default(),
         };
 
         //TODO add params and graph
@@ -274,7 +807,10 @@ impl OSCQueryHandler {
     }
 
     pub fn add_graph_item(&self, item: GraphItem) {
-        let item = Arc::new(item);
+        self.insert_graph_item(Arc::new(item));
+    }
+
+    fn insert_graph_item(&self, item: Arc<GraphItem>) {
         if let Ok(mut guard) = self.graph.lock() {
             let handle = self
                 .server
@@ -302,14 +838,53 @@ impl OSCQueryHandler {
             }
             self.add_params(item.clone() as _, handle.clone());
             guard.insert(item.uuid(), item.clone());
+            if let Ok(mut handles) = self.handles.lock() {
+                handles.insert(item.uuid(), handle.clone());
+            }
 
-            //TODO use some config to decide if we should start the event immediately
-            if let Some(e) = item.root_event() {
-                self.sched_queue
-                    .lock()
-                    .enqueue(0, e)
-                    .ok()
-                    .expect("to be able to schedule root event");
+            if let GraphItem::Root { .. } = item.as_ref() {
+                let owner = Arc::downgrade(&item);
+                let command_sender = self.command_sender.clone();
+                let id = item.uuid();
+                let _ = self
+                    .server
+                    .add_node(
+                        oscquery::node::GetSet::new(
+                            "active",
+                            Some("is the root active, set to start or stop it"),
+                            vec![ParamGetSet::Bool(
+                                ValueBuilder::new(Arc::new(GetSetFuncs::new(
+                                    move || {
+                                        owner
+                                            .upgrade()
+                                            .map(|o| o.root_active())
+                                            .flatten()
+                                            .unwrap_or(false)
+                                    },
+                                    move |v: bool| {
+                                        let id = id.clone().into();
+                                        let command = if v {
+                                            Command::RootStart { id, at: None }
+                                        } else {
+                                            Command::RootStop { id, at: None }
+                                        };
+                                        let request = CommandRequest {
+                                            request_id: None,
+                                            command,
+                                        };
+                                        if command_sender.send((Ok(request), None)).is_err() {
+                                            eprintln!("error sending command");
+                                        }
+                                    },
+                                )) as _)
+                                .build(),
+                            )],
+                            None,
+                        )
+                        .unwrap(),
+                        Some(handle),
+                    )
+                    .unwrap();
             }
 
             {
@@ -378,9 +953,27 @@ impl OSCQueryHandler {
                     Some(self.params_handle),
                 )
                 .unwrap();
+            if let Ok(mut handles) = self.handles.lock() {
+                handles.insert(param.uuid(), handle.clone());
+            }
             //value
             if let Some(shadow) = param.shadow() {
                 self.add_param_value(&shadow, handle);
+                //a value node for each key of a map, keys are sorted so this matches the value index
+                if let (true, Some(JsonValue::Object(keys))) =
+                    (param.type_name().starts_with("map::"), param.args())
+                {
+                    let keys_handle = self
+                        .server
+                        .add_node(
+                            oscquery::node::Container::new("keys", Some("values by key")).unwrap(),
+                            Some(handle),
+                        )
+                        .unwrap();
+                    for (index, key) in keys.keys().enumerate() {
+                        self.add_param_key_value(&shadow, key, index, keys_handle);
+                    }
+                }
             }
             //type node
             {
@@ -442,190 +1035,994 @@ impl OSCQueryHandler {
         }
     }
 
-    fn param_unbind(&self, owner: &ParamOwner, param_name: &str) {
-        if let Ok(params_guard) = self.params.lock() {
-            match owner {
-                //bind parameters
-                ParamOwner::Param(param_id) => {
-                    if let Some(param) = params_guard.get(&param_id) {
-                        param.params().unbind(param_name);
-                        //get handle and self.server.trigger(handle);
-                    }
-                }
-                ParamOwner::GraphItem(item_id) => {
-                    if let Ok(graph_guard) = self.graph.lock() {
-                        if let Some(item) = graph_guard.get(&item_id) {
-                            item.params().unbind(param_name);
-                            //get handle and self.server.trigger(handle);
-                        }
-                    }
-                }
+    fn resolve(&self, id: &IdOrAlias) -> Result<uuid::Uuid, CommandError> {
+        match id {
+            IdOrAlias::Id(id) => Ok(id.clone()),
+            IdOrAlias::Alias(name) => self
+                .aliases
+                .lock()
+                .unwrap()
+                .get(name)
+                .map(|(id, _)| id.clone())
+                .ok_or_else(|| CommandError::AliasNotFound(name.clone())),
+        }
+    }
+
+    fn resolve_owner(&self, owner: &ParamOwner<IdOrAlias>) -> Result<ParamOwner, CommandError> {
+        Ok(match owner {
+            ParamOwner::Param(id) => ParamOwner::Param(self.resolve(id)?),
+            ParamOwner::GraphItem(id) => ParamOwner::GraphItem(self.resolve(id)?),
+        })
+    }
+
+    fn resolve_children(
+        &self,
+        children: &GraphNodeChildren<IdOrAlias>,
+    ) -> Result<GraphNodeChildren, CommandError> {
+        Ok(match children {
+            GraphNodeChildren::None => GraphNodeChildren::None,
+            GraphNodeChildren::NChild(id) => GraphNodeChildren::NChild(self.resolve(id)?),
+            GraphNodeChildren::Indexed(ids) => {
+                GraphNodeChildren::Indexed(ids.iter().map(|id| self.resolve(id)).collect::<Result<
+                    Vec<_>,
+                    _,
+                >>(
+                )?)
             }
+        })
+    }
+
+    fn resolve_params(
+        &self,
+        params: &Option<HashMap<String, IdOrAlias>>,
+    ) -> Result<Option<HashMap<String, uuid::Uuid>>, CommandError> {
+        if let Some(params) = params {
+            let mut resolved = HashMap::new();
+            for (name, id) in params.iter() {
+                resolved.insert(name.clone(), self.resolve(id)?);
+            }
+            Ok(Some(resolved))
+        } else {
+            Ok(None)
         }
     }
 
-    fn param_bind(&self, owner: &ParamOwner, param_name: &str, param_id: &uuid::Uuid) {
-        if let Ok(params_guard) = self.params.lock() {
-            match owner {
-                //bind parameters
-                ParamOwner::Param(param_id) => {
-                    if let Some(parent) = params_guard.get(&param_id) {
-                        //TODO cycle detection
-                        //TODO error handling
-                        if let Some(param) = params_guard.get(&param_id) {
-                            let _r = parent.params().try_bind(param_name, param.clone());
-                            //get handle and self.server.trigger(handle);
-                        }
-                    }
-                }
-                ParamOwner::GraphItem(item_id) => {
-                    if let Ok(graph_guard) = self.graph.lock() {
-                        if let Some(item) = graph_guard.get(&item_id) {
-                            //TODO cycle detection
-                            //TODO error handling
-                            if let Some(param) = params_guard.get(&param_id) {
-                                let _r = item.params().try_bind(param_name, param.clone());
-                                //get handle and self.server.trigger(handle);
-                            }
-                        }
-                    }
-                }
+    //returns the id the alias previously referred to, if any
+    fn alias_set(&self, name: &str, id: &uuid::Uuid) -> Result<Option<uuid::Uuid>, CommandError> {
+        let node = alias_node(name, id)?;
+        if !self.params.lock().unwrap().contains_key(id)
+            && !self.graph.lock().unwrap().contains_key(id)
+        {
+            return Err(CommandError::IdNotFound(*id));
+        }
+        Ok(self.alias_insert(name, id, node))
+    }
+
+    //add a node built by `alias_node`, replacing any alias with the same name
+    //returns the id the alias previously referred to, if any
+    fn alias_insert(
+        &self,
+        name: &str,
+        id: &uuid::Uuid,
+        node: oscquery::node::Get,
+    ) -> Option<uuid::Uuid> {
+        let prev = self.alias_remove(name);
+        //the name is valid and no longer used by another alias
+        let handle = self
+            .server
+            .add_node(node, Some(self.aliases_handle))
+            .unwrap();
+        self.aliases
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), (id.clone(), handle));
+        prev
+    }
+
+    //returns the id the alias referred to, if there was an alias to remove
+    fn alias_remove(&self, name: &str) -> Option<uuid::Uuid> {
+        if let Some((id, handle)) = self.aliases.lock().unwrap().remove(name) {
+            let _ = self.server.rm_node(handle);
+            Some(id)
+        } else {
+            None
+        }
+    }
+
+    //remove all the aliases that refer to the given id
+    fn alias_remove_id(&self, id: &uuid::Uuid) {
+        let names: Vec<String> = self
+            .aliases
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, (alias_id, _))| alias_id == id)
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in names {
+            self.alias_remove(&name);
+        }
+    }
+
+    //get the item that owns the params
+    fn param_owner(
+        &self,
+        params: &HashMap<uuid::Uuid, Arc<Param>>,
+        owner: &ParamOwner,
+    ) -> Result<Arc<dyn ParamMapGet + Send + Sync>, CommandError> {
+        match owner {
+            ParamOwner::Param(id) => params
+                .get(id)
+                .map(|p| p.clone() as _)
+                .ok_or(CommandError::ParamNotFound(*id)),
+            ParamOwner::GraphItem(id) => self
+                .graph
+                .lock()
+                .unwrap()
+                .get(id)
+                .map(|i| i.clone() as _)
+                .ok_or(CommandError::GraphItemNotFound(*id)),
+        }
+    }
+
+    //the scheduled changes that haven't been applied yet
+    fn pending(&self) -> std::sync::MutexGuard<'_, pending::Pending> {
+        let mut pending = self.pending.lock().unwrap();
+        pending.prune(self.queue_sources.tick_next());
+        pending
+    }
+
+    //refuse to remove an item that a scheduled change still uses
+    fn pending_check(&self, id: &uuid::Uuid) -> CommandResult {
+        if let Some(tick) = self.pending().uses_id(id) {
+            Err(CommandError::Pending { id: *id, tick })
+        } else {
+            Ok(())
+        }
+    }
+
+    //make sure that binding `param_id` to `owner` won't create a cycle, including the bindings
+    //that pending changes and the earlier changes of the `batch` will make
+    fn param_cycle_check(
+        &self,
+        params: &HashMap<uuid::Uuid, Arc<Param>>,
+        owner: &ParamOwner,
+        param_id: &uuid::Uuid,
+        batch: &pending::Edges,
+    ) -> CommandResult {
+        if let ParamOwner::Param(owner_id) = owner {
+            let pending = self.pending();
+            let edges = |id: &uuid::Uuid| {
+                let mut deps = params
+                    .get(id)
+                    .map(|p| p.params().uuids())
+                    .unwrap_or_default();
+                deps.extend(pending.param_deps(id));
+                deps.extend(batch.param_deps(id));
+                deps
+            };
+            if creates_cycle(owner_id, param_id, edges) {
+                return Err(CommandError::Cycle {
+                    from: *owner_id,
+                    to: *param_id,
+                });
             }
         }
+        Ok(())
     }
 
-    fn param_create(
+    //make sure that giving `parent_id` the `children_ids` won't create a cycle, including the
+    //children that pending changes and the earlier changes of the `batch` will set
+    //
+    //children that pending changes will replace are still included, so this may refuse changes
+    //that would only be cycles until then
+    fn graph_cycle_check(
         &self,
-        uuid: &Option<uuid::Uuid>,
-        type_name: &str,
-        args: JsonValue,
+        graph: &HashMap<uuid::Uuid, Arc<GraphItem>>,
+        parent_id: &uuid::Uuid,
+        children_ids: &[uuid::Uuid],
+        batch: &pending::Edges,
+    ) -> CommandResult {
+        let pending = self.pending();
+        let edges = |id: &uuid::Uuid| {
+            let mut deps = graph
+                .get(id)
+                .map(|i| i.children_uuids())
+                .flatten()
+                .unwrap_or_default();
+            deps.extend(pending.child_deps(id));
+            deps.extend(batch.child_deps(id));
+            deps
+        };
+        if let Some(child_id) = children_ids
+            .iter()
+            .find(|child_id| creates_cycle(parent_id, child_id, edges))
+        {
+            Err(CommandError::Cycle {
+                from: *parent_id,
+                to: *child_id,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    //returns the id of the param that was bound, if any
+    fn param_unbind(
+        &self,
+        owner: &ParamOwner,
+        param_name: &str,
+    ) -> Result<Option<uuid::Uuid>, CommandError> {
+        let params_guard = self.params.lock().unwrap();
+        let params = self.param_owner(&params_guard, owner)?;
+        if params.params().contains_key(param_name) {
+            //get handle and self.server.trigger(handle);
+            Ok(params.params().unbind(param_name).map(|p| p.uuid()))
+        } else {
+            Err(CommandError::Binding {
+                owner: owner.uuid(),
+                param_name: param_name.to_string(),
+                param_id: None,
+                error: BindingError::KeyMissing,
+            })
+        }
+    }
+
+    //returns the id of the param that was previously bound, if any
+    fn param_bind(
+        &self,
+        owner: &ParamOwner,
+        param_name: &str,
+        param_id: &uuid::Uuid,
+    ) -> Result<Option<uuid::Uuid>, CommandError> {
+        let params_guard = self.params.lock().unwrap();
+        let param = params_guard
+            .get(param_id)
+            .ok_or(CommandError::ParamNotFound(*param_id))?
+            .clone();
+        self.param_cycle_check(&params_guard, owner, param_id, &Default::default())?;
+        let params = self.param_owner(&params_guard, owner)?;
+        let prev = params.params().uuid(param_name);
+        params
+            .params()
+            .try_bind(param_name, param)
+            .map(|_| prev)
+            .map_err(|error| CommandError::Binding {
+                owner: owner.uuid(),
+                param_name: param_name.to_string(),
+                param_id: Some(*param_id),
+                error,
+            })
+    }
+
+    //bind all the params, returns the first error but attempts every binding
+    fn param_bind_all(
+        &self,
+        owner: &ParamOwner,
         params: &Option<HashMap<String, uuid::Uuid>>,
-    ) {
-        let uuid = uuid.map_or_else(|| uuid::Uuid::new_v4(), |u| u.clone());
-        match crate::param::factory::create_param(&uuid, type_name, args) {
-            Ok(inst) => {
-                self.add_param(Arc::new(inst));
-                if let Some(params) = params {
-                    let owner = ParamOwner::Param(uuid);
-                    for (name, id) in params.iter() {
-                        self.param_bind(&owner, name, id);
-                    }
+    ) -> CommandResult {
+        let mut result = Ok(());
+        if let Some(params) = params {
+            for (name, id) in params.iter() {
+                let r = self.param_bind(owner, name, id);
+                if result.is_ok() {
+                    result = r.map(|_| ());
                 }
             }
-            Err(e) => println!("error creating instance {}", e),
         }
+        result
+    }
+
+    fn param_create(&self, uuid: &uuid::Uuid, type_name: &str, args: JsonValue) -> CommandResult {
+        if self.params.lock().unwrap().contains_key(uuid) {
+            return Err(CommandError::IdExists(*uuid));
+        }
+        let inst = crate::param::factory::create_param(uuid, type_name, args, &self.queue_sources)
+            .map_err(|error| CommandError::Create {
+                id: uuid.clone(),
+                type_name: type_name.to_string(),
+                error,
+            })?;
+        self.add_param(Arc::new(inst));
+        Ok(())
     }
 
     fn graph_node_create(
         &self,
-        uuid: &Option<uuid::Uuid>,
+        uuid: &uuid::Uuid,
         type_name: &str,
         args: Option<JsonValue>,
-        children: &Option<GraphNodeChildren>,
-        params: &Option<HashMap<String, uuid::Uuid>>,
-    ) {
-        let uuid = uuid.map_or_else(|| uuid::Uuid::new_v4(), |u| u.clone());
-        match crate::graph::factory::create_instance(&uuid, &type_name, args, &self.queue_sources) {
-            Ok(item) => {
-                self.add_graph_item(item);
-                if let Some(children) = children {
-                    self.graph_node_set_children(&uuid, children);
-                }
-                if let Some(params) = params {
-                    let owner = ParamOwner::GraphItem(uuid.clone());
-                    for (name, id) in params.iter() {
-                        self.param_bind(&owner, name, id);
+    ) -> CommandResult {
+        if self.graph.lock().unwrap().contains_key(uuid) {
+            return Err(CommandError::IdExists(*uuid));
+        }
+        let item =
+            crate::graph::factory::create_instance(uuid, &type_name, args, &self.queue_sources)
+                .map_err(|error| CommandError::Create {
+                    id: uuid.clone(),
+                    type_name: type_name.to_string(),
+                    error,
+                })?;
+        self.add_graph_item(item);
+        Ok(())
+    }
+
+    //lookup the graph nodes for the given children with `get`, the error is the first missing id
+    fn graph_children(
+        get: &dyn Fn(&uuid::Uuid) -> Option<Arc<GraphItem>>,
+        children: &GraphNodeChildren,
+    ) -> Result<ChildrenUUIDs, uuid::Uuid> {
+        match children {
+            GraphNodeChildren::None => Ok((Arc::new(Children::None), vec![])),
+            GraphNodeChildren::Indexed(children_ids) => {
+                let mut children = Vec::new();
+                for id in children_ids.iter() {
+                    if let Some(child) = get(id).map(|c| c.get_node()).flatten() {
+                        children.push(child);
+                    } else {
+                        return Err(id.clone());
                     }
                 }
+                Ok((
+                    Arc::new(Children::Indexed { children }),
+                    children_ids.clone(),
+                ))
+            }
+            GraphNodeChildren::NChild(child_id) => {
+                if let Some(child) = get(child_id).map(|c| c.get_node()).flatten() {
+                    Ok((Arc::new(Children::NChild { child }), vec![child_id.clone()]))
+                } else {
+                    Err(child_id.clone())
+                }
             }
-            Err(e) => println!("error creating instance {}", e),
         }
     }
 
-    fn graph_node_set_children(&self, parent_id: &uuid::Uuid, children: &GraphNodeChildren) {
-        if let Ok(guard) = self.graph.lock() {
-            if let Some(parent) = guard.get(parent_id) {
-                match children {
-                    GraphNodeChildren::None => {
-                        let _ = parent.children_swap((Arc::new(Children::None), vec![]));
-                    }
-                    GraphNodeChildren::Indexed(children_ids) => {
-                        let children: Option<Vec<GraphNodeContainer>> = children_ids
-                            .iter()
-                            .map(|id| {
-                                guard
-                                    .get(&id)
-                                    .map(|i| i.get_node().map(|n| n.clone()))
-                                    .flatten()
-                            })
-                            .collect();
-                        if let Some(children) = children {
-                            let _ = parent.children_swap((
-                                Arc::new(Children::Indexed { children }),
-                                children_ids.clone(),
-                            ));
-                        } else {
-                            eprintln!("cannot find graph children with ids {:?}", children_ids);
-                        }
+    //returns the children that were replaced
+    fn graph_node_set_children(
+        &self,
+        parent_id: &uuid::Uuid,
+        children: &GraphNodeChildren,
+    ) -> Result<GraphNodeChildren, CommandError> {
+        let guard = self.graph.lock().unwrap();
+        let parent = guard
+            .get(parent_id)
+            .ok_or(CommandError::GraphItemNotFound(*parent_id))?;
+        let children = Self::graph_children(&|id| guard.get(id).cloned(), children)
+            .map_err(CommandError::GraphItemNotFound)?;
+        self.graph_cycle_check(&guard, parent_id, &children.1, &Default::default())?;
+        match parent.children_swap(children) {
+            Ok(old) => {
+                let prev = (&old).into();
+                self.dispose(old);
+                Ok(prev)
+            }
+            Err(_) => Err(CommandError::NoChildren(*parent_id)),
+        }
+    }
+
+    //hand off to the dispose thread so that nothing is freed in the scheduler thread
+    fn dispose<T: Send + 'static>(&self, item: T) {
+        let _ = self.dispose_sender.try_send(Box::new(item));
+    }
+
+    //remove the oscquery node, and its children, for the given id
+    fn remove_node(&self, id: &uuid::Uuid) {
+        if let Ok(mut handles) = self.handles.lock() {
+            if let Some(handle) = handles.remove(id) {
+                let _ = self.server.rm_node(handle);
+            }
+        }
+    }
+
+    fn param_delete(&self, id: &uuid::Uuid) -> CommandResult {
+        self.pending_check(id)?;
+        let mut params_guard = self.params.lock().unwrap();
+        let param = params_guard
+            .remove(id)
+            .ok_or(CommandError::ParamNotFound(*id))?;
+        //unbind from everything that depends on this param
+        for p in params_guard.values() {
+            unbind_matching(p.params(), id);
+        }
+        for item in self.graph.lock().unwrap().values() {
+            unbind_matching(item.params(), id);
+        }
+        //release our own bindings
+        unbind_all(param.params());
+        self.alias_remove_id(id);
+        self.remove_node(id);
+        self.dispose(param);
+        Ok(())
+    }
+
+    fn graph_item_delete(&self, id: &uuid::Uuid) -> CommandResult {
+        self.pending_check(id)?;
+        let mut guard = self.graph.lock().unwrap();
+        let item = guard
+            .remove(id)
+            .ok_or(CommandError::GraphItemNotFound(*id))?;
+        item.root_deactivate();
+        self.root_starts.lock().unwrap().remove(id);
+        //remove from any parents
+        for parent in guard.values() {
+            if let Some(uuids) = parent.children_uuids() {
+                if !uuids.contains(id) {
+                    continue;
+                }
+                let children = match parent.children_type_name() {
+                    Some("NChild") => GraphNodeChildren::None,
+                    _ => {
+                        GraphNodeChildren::Indexed(uuids.into_iter().filter(|u| u != id).collect())
                     }
-                    GraphNodeChildren::NChild(child_id) => {
-                        if let Some(child) = guard.get(&child_id).map(|c| c.get_node()).flatten() {
-                            let _ = parent.children_swap((
-                                Arc::new(Children::NChild { child }),
-                                vec![child_id.clone()],
-                            ));
-                        } else {
-                            eprintln!("cannot find graph child with id {:?}", child_id);
-                        }
+                };
+                //the remaining children are all in the graph
+                if let Ok(children) = Self::graph_children(&|id| guard.get(id).cloned(), &children)
+                {
+                    if let Ok(old) = parent.children_swap(children) {
+                        self.dispose(old);
                     }
                 }
-            } else {
-                eprintln!("cannot find graph parent with id {}", parent_id);
             }
         }
+        //release our own children and bindings
+        if let Ok(old) = item.children_swap((Arc::new(Children::None), vec![])) {
+            self.dispose(old);
+        }
+        unbind_all(item.params());
+        self.alias_remove_id(id);
+        self.remove_node(id);
+        self.dispose(item);
+        Ok(())
     }
 
-    fn handle_command(&self, cmd: &Command) {
+    //apply a command, logging how to undo it
+    fn apply(&self, cmd: &Command, log: &mut ApplyLog) -> CommandResult {
         match cmd {
             Command::Batch(b) => {
-                for c in b.iter() {
-                    self.handle_command(c);
+                let errors: Vec<(usize, CommandError)> = b
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, c)| self.apply(c, log).err().map(|e| (i, e)))
+                    .collect();
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(CommandError::Batch(errors))
                 }
             }
+            Command::AtomicBatch(b) => self.apply_atomic(b, log),
             Command::ParamBind {
                 owner,
                 param_name,
                 param_id,
-            } => self.param_bind(&owner, param_name.as_str(), &param_id),
+            } => {
+                let owner = self.resolve_owner(owner)?;
+                let param_id = self.resolve(param_id)?;
+                let prev = self.param_bind(&owner, param_name, &param_id)?;
+                log.undo.push(rebind(&owner, param_name, prev));
+                log.done.push(rebind(&owner, param_name, Some(param_id)));
+                Ok(())
+            }
             Command::ParamUnbind { owner, param_name } => {
-                self.param_unbind(owner, param_name.as_str())
+                let owner = self.resolve_owner(owner)?;
+                if let Some(prev) = self.param_unbind(&owner, param_name)? {
+                    log.undo.push(rebind(&owner, param_name, Some(prev)));
+                    log.done.push(rebind(&owner, param_name, None));
+                }
+                Ok(())
             }
             Command::ParamCreate {
                 id,
                 type_name,
                 args,
                 params,
-            } => self.param_create(id, type_name, args.clone(), params),
+            } => {
+                let id = id.unwrap_or_else(|| uuid::Uuid::new_v4());
+                let params = self.resolve_params(params)?;
+                self.param_create(&id, type_name, args.clone())?;
+                //deleting also removes the bindings
+                log.undo.push(Command::ParamDelete { id: id.into() });
+                log.done.push(Command::ParamCreate {
+                    id: Some(id),
+                    type_name: type_name.clone(),
+                    args: args.clone(),
+                    params: resolved(&params),
+                });
+                self.param_bind_all(&ParamOwner::Param(id), &params)
+            }
             Command::GraphItemCreate {
                 id,
                 type_name,
                 args,
                 children,
                 params,
-            } => self.graph_node_create(id, type_name, args.clone(), children, params),
+                autostart,
+            } => {
+                let id = id.unwrap_or_else(|| uuid::Uuid::new_v4());
+                let children = if let Some(children) = children {
+                    Some(self.resolve_children(children)?)
+                } else {
+                    None
+                };
+                let params = self.resolve_params(params)?;
+                self.graph_node_create(&id, type_name, args.clone())?;
+                //deleting also removes the children and bindings
+                log.undo.push(Command::GraphItemDelete { id: id.into() });
+                log.done.push(Command::GraphItemCreate {
+                    id: Some(id),
+                    type_name: type_name.clone(),
+                    args: args.clone(),
+                    children: children.clone().map(|c| c.into()),
+                    params: resolved(&params),
+                    autostart: *autostart,
+                });
+                if autostart.unwrap_or(true) {
+                    log.roots.push(id.clone());
+                }
+                let children = if let Some(children) = children {
+                    self.graph_node_set_children(&id, &children).map(|_| ())
+                } else {
+                    Ok(())
+                };
+                let params = self.param_bind_all(&ParamOwner::GraphItem(id), &params);
+                children.and(params)
+            }
+            Command::GraphNodeSetChildren {
+                parent_id,
+                children,
+            } => {
+                let parent_id = self.resolve(parent_id)?;
+                let children = self.resolve_children(children)?;
+                let prev = self.graph_node_set_children(&parent_id, &children)?;
+                log.undo.push(Command::GraphNodeSetChildren {
+                    parent_id: parent_id.into(),
+                    children: prev.into(),
+                });
+                log.done.push(Command::GraphNodeSetChildren {
+                    parent_id: parent_id.into(),
+                    children: children.into(),
+                });
+                Ok(())
+            }
+            Command::ParamDelete { id } => {
+                let id = self.resolve(id)?;
+                self.param_delete(&id)?;
+                log.irreversible = true;
+                Ok(())
+            }
+            Command::GraphItemDelete { id } => {
+                let id = self.resolve(id)?;
+                self.graph_item_delete(&id)?;
+                log.irreversible = true;
+                Ok(())
+            }
+            Command::AliasSet { name, id } => {
+                let id = self.resolve(id)?;
+                let prev = self.alias_set(name, &id)?;
+                log.undo.push(if let Some(prev) = prev {
+                    Command::AliasSet {
+                        name: name.clone(),
+                        id: prev.into(),
+                    }
+                } else {
+                    Command::AliasRemove { name: name.clone() }
+                });
+                log.done.push(Command::AliasSet {
+                    name: name.clone(),
+                    id: id.into(),
+                });
+                Ok(())
+            }
+            Command::AliasRemove { name } => {
+                let prev = self
+                    .alias_remove(name)
+                    .ok_or_else(|| CommandError::AliasNotFound(name.clone()))?;
+                log.undo.push(Command::AliasSet {
+                    name: name.clone(),
+                    id: prev.into(),
+                });
+                log.done.push(Command::AliasRemove { name: name.clone() });
+                Ok(())
+            }
+            Command::RootStart { id, at: Some(at) } => self.schedule(
+                at,
+                &Command::RootStart {
+                    id: id.clone(),
+                    at: None,
+                },
+            ),
+            Command::RootStop { id, at: Some(at) } => self.schedule(
+                at,
+                &Command::RootStop {
+                    id: id.clone(),
+                    at: None,
+                },
+            ),
+            Command::RootRestart { id, at: Some(at) } => self.schedule(
+                at,
+                &Command::RootRestart {
+                    id: id.clone(),
+                    at: None,
+                },
+            ),
+            Command::RootStart { id, at: None } => {
+                let item = self.root(&self.resolve(id)?)?;
+                if item.root_active() != Some(true) {
+                    log.roots.push(item.uuid());
+                }
+                Ok(())
+            }
+            Command::RootStop { id, at: None } => {
+                let item = self.root(&self.resolve(id)?)?;
+                item.root_deactivate();
+                self.root_starts.lock().unwrap().remove(&item.uuid());
+                Ok(())
+            }
+            Command::RootRestart { id, at: None } => {
+                let item = self.root(&self.resolve(id)?)?;
+                log.roots.push(item.uuid());
+                Ok(())
+            }
+            Command::Dump { format } => {
+                log.output = Some(match format.unwrap_or(dump::DumpFormat::Json) {
+                    dump::DumpFormat::Json => dump::json(&self.params, &self.graph),
+                    dump::DumpFormat::Dot => {
+                        JsonValue::String(dump::dot(&self.params, &self.graph))
+                    }
+                });
+                Ok(())
+            }
+            Command::SessionSave { path } => self.session_save(path),
+            Command::SessionLoad { path } => {
+                self.session_load(path)?;
+                log.irreversible = true;
+                Ok(())
+            }
+            Command::Undo => self.undo(),
+            Command::Redo => self.redo(),
+            Command::Scheduled { at, command } => self.schedule(at, command),
+        }
+    }
+
+    //get the graph item with the given id, if it is a root
+    fn root(&self, id: &uuid::Uuid) -> Result<Arc<GraphItem>, CommandError> {
+        let item = self
+            .graph
+            .lock()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or(CommandError::GraphItemNotFound(*id))?;
+        if item.root_active().is_some() {
+            Ok(item)
+        } else {
+            Err(CommandError::NotRoot(*id))
+        }
+    }
+
+    //prepare a command and schedule its changes to be applied at the given time
+    fn schedule(&self, at: &ScheduleAt, cmd: &Command) -> CommandResult {
+        let mut prepared = Prepared::default();
+        self.prepare(cmd, &mut prepared)?;
+        let tick = self.schedule_tick(at)?;
+        let now = self.queue_sources.tick_next();
+        self.enqueue_changes(tick, prepared.changes, prepared.events)?;
+        //ticks that have already passed are applied at the next tick
+        self.pending()
+            .push(std::cmp::max(tick, now), prepared.edges);
+        let mut starts = self.root_starts.lock().unwrap();
+        for id in prepared.stopped {
+            starts.remove(&id);
+        }
+        for id in prepared.started {
+            starts.insert(id, tick);
+        }
+        Ok(())
+    }
+
+    //enqueue the changes, if there are any, and the gated root events all at `tick`
+    //
+    //if any of them can't be enqueued the gates of those that were are closed, before the
+    //scheduler can dequeue them, so either all of them take effect or none of them do
+    fn enqueue_changes(
+        &self,
+        tick: usize,
+        changes: Vec<Change>,
+        events: Vec<(EventContainer, Arc<Atomic<bool>>)>,
+    ) -> CommandResult {
+        let mut gates = Vec::new();
+        let mut queue = self.sched_queue.lock();
+        let mut enqueue = |event: EventContainer, gate: Arc<Atomic<bool>>| {
+            if queue.enqueue(tick, event).is_ok() {
+                gates.push(gate);
+                Ok(())
+            } else {
+                for gate in gates.iter() {
+                    gate.store(false, Ordering::Release);
+                }
+                Err(CommandError::ScheduleFailed)
+            }
+        };
+        if !changes.is_empty() {
+            let event = ChangeEvent::new(changes);
+            let gate = event.gate();
+            enqueue(EventContainer::new(event), gate)?;
+        }
+        for (event, gate) in events {
+            enqueue(event, gate)?;
+        }
+        Ok(())
+    }
+
+    //validate a command and collect its changes, to be applied later in the scheduler thread
+    fn prepare(&self, cmd: &Command, prepared: &mut Prepared) -> CommandResult {
+        match cmd {
+            Command::Batch(b) | Command::AtomicBatch(b) => {
+                for c in b.iter() {
+                    self.prepare(c, prepared)?;
+                }
+                Ok(())
+            }
+            Command::RootStart { id, at: None } | Command::RootRestart { id, at: None } => {
+                let item = self.root(&self.resolve(id)?)?;
+                if let Command::RootStart { .. } = cmd {
+                    if item.root_active() == Some(true) {
+                        return Ok(());
+                    }
+                }
+                if let Some((event, gate)) = item.root_event_gate() {
+                    prepared.events.push((event, gate.clone()));
+                    prepared.started.push(item.uuid());
+                    prepared.edges.uses(&item.uuid());
+                    prepared.changes.push(Change::RootActivate {
+                        item,
+                        gate: Some(gate),
+                    });
+                }
+                Ok(())
+            }
+            Command::RootStop { id, at: None } => {
+                let item = self.root(&self.resolve(id)?)?;
+                prepared.stopped.push(item.uuid());
+                prepared.edges.uses(&item.uuid());
+                prepared.changes.push(Change::RootDeactivate { item });
+                Ok(())
+            }
+            Command::ParamBind {
+                owner,
+                param_name,
+                param_id,
+            } => {
+                let owner = self.resolve_owner(owner)?;
+                let param_id = self.resolve(param_id)?;
+                let params_guard = self.params.lock().unwrap();
+                let param = params_guard
+                    .get(&param_id)
+                    .ok_or(CommandError::ParamNotFound(param_id))?
+                    .clone();
+                self.param_cycle_check(&params_guard, &owner, &param_id, &prepared.edges)?;
+                let params = self.param_owner(&params_guard, &owner)?;
+                params
+                    .params()
+                    .can_bind(param_name, &param)
+                    .map_err(|error| CommandError::Binding {
+                        owner: owner.uuid(),
+                        param_name: param_name.clone(),
+                        param_id: Some(param_id),
+                        error,
+                    })?;
+                prepared.edges.bind(&owner, &param_id);
+                prepared.changes.push(Change::Bind {
+                    owner: params,
+                    name: param_name.clone(),
+                    param,
+                    error: None,
+                });
+                Ok(())
+            }
+            Command::ParamUnbind { owner, param_name } => {
+                let owner = self.resolve_owner(owner)?;
+                let params = self.param_owner(&self.params.lock().unwrap(), &owner)?;
+                if !params.params().contains_key(param_name) {
+                    return Err(CommandError::Binding {
+                        owner: owner.uuid(),
+                        param_name: param_name.clone(),
+                        param_id: None,
+                        error: BindingError::KeyMissing,
+                    });
+                }
+                prepared.edges.uses(&owner.uuid());
+                prepared.changes.push(Change::Unbind {
+                    owner: params,
+                    name: param_name.clone(),
+                    unbound: None,
+                });
+                Ok(())
+            }
             Command::GraphNodeSetChildren {
                 parent_id,
                 children,
-            } => self.graph_node_set_children(parent_id, children),
+            } => {
+                let parent_id = self.resolve(parent_id)?;
+                let children = self.resolve_children(children)?;
+                let guard = self.graph.lock().unwrap();
+                let parent = guard
+                    .get(&parent_id)
+                    .ok_or(CommandError::GraphItemNotFound(parent_id))?
+                    .clone();
+                if parent.children_uuids().is_none() {
+                    return Err(CommandError::NoChildren(parent_id));
+                }
+                let children = Self::graph_children(&|id| guard.get(id).cloned(), &children)
+                    .map_err(CommandError::GraphItemNotFound)?;
+                self.graph_cycle_check(&guard, &parent_id, &children.1, &prepared.edges)?;
+                prepared.edges.children(&parent_id, &children.1);
+                prepared.changes.push(Change::ChildrenSwap {
+                    item: parent,
+                    children: Some(children),
+                });
+                Ok(())
+            }
+            _ => Err(CommandError::NotSchedulable),
+        }
+    }
+
+    //compute the absolute tick for a scheduled command
+    fn schedule_tick(&self, at: &ScheduleAt) -> Result<usize, CommandError> {
+        let now = self.queue_sources.tick_next();
+        Ok(match at {
+            ScheduleAt::Tick(tick) => *tick,
+            ScheduleAt::Delay(ticks) => now + ticks,
+            ScheduleAt::Quantized { ticks, root } => {
+                let start = if let Some(root) = root {
+                    let id = self.resolve(root)?;
+                    self.root_starts
+                        .lock()
+                        .unwrap()
+                        .get(&id)
+                        .cloned()
+                        .ok_or(CommandError::RootNotActive(id))?
+                } else {
+                    0
+                };
+                let ticks = std::cmp::max(1, *ticks);
+                let elapsed = now.saturating_sub(start);
+                start + ((elapsed + ticks - 1) / ticks) * ticks
+            }
+        })
+    }
+
+    //apply the undo commands, most recent first, errors are paired with the index of the undo
+    //command that caused them
+    fn rollback(&self, undo: &[Command]) -> CommandResult {
+        let mut log = ApplyLog::default();
+        let errors: Vec<(usize, CommandError)> = undo
+            .iter()
+            .enumerate()
+            .rev()
+            .filter_map(|(i, cmd)| self.apply(cmd, &mut log).err().map(|e| (i, e)))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(CommandError::Batch(errors))
+        }
+    }
+
+    //start any of the items that are roots, all at the same tick
+    fn start_roots(&self, roots: &[uuid::Uuid]) {
+        let guard = self.graph.lock().unwrap();
+        let mut starts = self.root_starts.lock().unwrap();
+        let mut queue = self.sched_queue.lock();
+        let now = self.queue_sources.tick_next();
+        for item in roots.iter().filter_map(|id| guard.get(id)) {
+            if let Some(e) = item.root_event() {
+                queue
+                    .enqueue(now, e)
+                    .ok()
+                    .expect("to be able to schedule root event");
+                starts.insert(item.uuid(), now);
+            }
+        }
+    }
+
+    fn handle_command(&self, cmd: &Command) -> CommandOutput {
+        let mut log = ApplyLog::default();
+        let result = self.apply(cmd, &mut log);
+        self.start_roots(&log.roots);
+        if log.irreversible {
+            self.undo_history.clear();
+            self.redo_history.clear();
+        } else if !log.undo.is_empty() {
+            self.undo_history.push(HistoryEntry {
+                done: log.done,
+                undo: log.undo,
+            });
+            self.redo_history.clear();
+        }
+        self.server.trigger(self.undo_depth_handle);
+        self.server.trigger(self.redo_depth_handle);
+        result.map(|_| log.output)
+    }
+
+    fn undo(&self) -> CommandResult {
+        let entry = self.undo_history.pop().ok_or(CommandError::NothingToUndo)?;
+        let result = self.rollback(&entry.undo);
+        self.redo_history.push(entry);
+        result
+    }
+
+    fn redo(&self) -> CommandResult {
+        let entry = self.redo_history.pop().ok_or(CommandError::NothingToRedo)?;
+        let mut log = ApplyLog::default();
+        let result = entry
+            .done
+            .iter()
+            .map(|cmd| self.apply(cmd, &mut log))
+            .fold(Ok(()), |acc, r| acc.and(r));
+        self.start_roots(&log.roots);
+        if !log.undo.is_empty() {
+            self.undo_history.push(HistoryEntry {
+                done: log.done,
+                undo: log.undo,
+            });
+        }
+        result
+    }
+
+    //send the result back to the sender and publish errors
+    fn report(
+        &self,
+        request_id: &Option<String>,
+        result: &CommandOutput,
+        addr: Option<SocketAddr>,
+    ) {
+        let response = serde_json::to_string(&CommandResponse { request_id, result })
+            .expect("failed to serialize");
+        if result.is_err() {
+            eprintln!("command failed {}", response);
+            if let Ok(mut e) = self.last_error.0.lock() {
+                *e = response.clone();
+            }
+            self.server.trigger(self.last_error_handle);
+        }
+        if let Some(addr) = addr {
+            let packet = OscPacket::Message(OscMessage {
+                addr: "/xsched/command/result".into(),
+                args: vec![OscType::String(response)],
+            });
+            if let Ok(buf) = oscquery::osc::encoder::encode(&packet) {
+                if self.reply_socket.send_to(&buf, addr).is_err() {
+                    eprintln!("failed to send command result to {}", addr);
+                }
+            }
         }
     }
 
     //TODO timeout?
     pub fn process(&mut self) {
-        while let Ok(cmd) = self.command_receiver.try_recv() {
-            self.handle_command(&cmd);
+        for request in self.replay_due(self.queue_sources.tick_next()) {
+            self.process_request(request, None);
+        }
+        while let Ok((request, addr)) = self.command_receiver.try_recv() {
+            match request {
+                Ok(request) => self.process_request(request, addr),
+                Err(e) => self.report(&None, &Err(e), addr),
+            }
         }
     }
+
+    fn process_request(&mut self, request: CommandRequest, addr: Option<SocketAddr>) {
+        let command = self.resolve_ticks(request.command.with_ids());
+        let journaled = self.journaled(&command);
+        let result = self.handle_command(&command);
+        let request = self.journal_write(CommandRequest {
+            request_id: request.request_id,
+            command: journaled.unwrap_or(command),
+        });
+        self.report(&request.request_id, &result, addr);
+    }
 }
 
 //pull in the codegen
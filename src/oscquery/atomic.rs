@@ -0,0 +1,525 @@
+//! Atomic batches: every command is validated, and new items are created and bound, before
+//! anything is applied. Changes to existing items are then applied together, and created roots
+//! are started, at a single tick in the scheduler thread.
+
+use super::{
+    alias_node, graph_node_children, pending, rebind, resolved, ApplyLog, Command, CommandResult,
+    GraphNodeChildren, IdOrAlias, OSCQueryHandler, ParamOwner,
+};
+use crate::{
+    change::Change,
+    error::CommandError,
+    graph::GraphItem,
+    param::{BindingError, Param, ParamMapGet},
+};
+use std::{collections::HashMap, sync::Arc};
+
+//the items and changes of an atomic batch, validated but not yet applied
+#[derive(Default)]
+struct Staged {
+    //created items, not visible to anything else until the batch is applied
+    params: HashMap<uuid::Uuid, Arc<Param>>,
+    graph: HashMap<uuid::Uuid, Arc<GraphItem>>,
+    //created roots to start
+    roots: Vec<uuid::Uuid>,
+    //alias changes in order, with the validated alias node, `None` removes the alias
+    aliases: Vec<(String, Option<(uuid::Uuid, oscquery::node::Get)>)>,
+    //changes to existing items, to be applied in the scheduler thread
+    changes: Vec<Change>,
+    //every dependency the batch adds, including those of created items, for cycle checks
+    edges: pending::Edges,
+    //the dependencies added by, and the items used by, the changes
+    pending: pending::Edges,
+    //the staged bindings and children of existing items, for logging what they replace
+    bindings: HashMap<(uuid::Uuid, String), Option<uuid::Uuid>>,
+    children: HashMap<uuid::Uuid, GraphNodeChildren>,
+}
+
+impl Staged {
+    fn param(&self, handler: &OSCQueryHandler, id: &uuid::Uuid) -> Option<Arc<Param>> {
+        self.params
+            .get(id)
+            .cloned()
+            .or_else(|| handler.params.lock().unwrap().get(id).cloned())
+    }
+
+    fn graph_item(&self, handler: &OSCQueryHandler, id: &uuid::Uuid) -> Option<Arc<GraphItem>> {
+        self.graph
+            .get(id)
+            .cloned()
+            .or_else(|| handler.graph.lock().unwrap().get(id).cloned())
+    }
+
+    fn is_staged(&self, id: &uuid::Uuid) -> bool {
+        self.params.contains_key(id) || self.graph.contains_key(id)
+    }
+
+    //the id an alias will refer to, if the batch changes it
+    fn alias(&self, name: &str) -> Option<Option<uuid::Uuid>> {
+        self.aliases
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, alias)| alias.as_ref().map(|(id, _)| *id))
+    }
+
+    fn resolve(
+        &self,
+        handler: &OSCQueryHandler,
+        id: &IdOrAlias,
+    ) -> Result<uuid::Uuid, CommandError> {
+        match id {
+            IdOrAlias::Alias(name) => match self.alias(name) {
+                Some(Some(id)) => Ok(id),
+                Some(None) => Err(CommandError::AliasNotFound(name.clone())),
+                None => handler.resolve(id),
+            },
+            IdOrAlias::Id(_) => handler.resolve(id),
+        }
+    }
+
+    fn resolve_owner(
+        &self,
+        handler: &OSCQueryHandler,
+        owner: &ParamOwner<IdOrAlias>,
+    ) -> Result<ParamOwner, CommandError> {
+        Ok(match owner {
+            ParamOwner::Param(id) => ParamOwner::Param(self.resolve(handler, id)?),
+            ParamOwner::GraphItem(id) => ParamOwner::GraphItem(self.resolve(handler, id)?),
+        })
+    }
+
+    fn resolve_children(
+        &self,
+        handler: &OSCQueryHandler,
+        children: &GraphNodeChildren<IdOrAlias>,
+    ) -> Result<GraphNodeChildren, CommandError> {
+        Ok(match children {
+            GraphNodeChildren::None => GraphNodeChildren::None,
+            GraphNodeChildren::NChild(id) => GraphNodeChildren::NChild(self.resolve(handler, id)?),
+            GraphNodeChildren::Indexed(ids) => GraphNodeChildren::Indexed(
+                ids.iter()
+                    .map(|id| self.resolve(handler, id))
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        })
+    }
+
+    fn resolve_params(
+        &self,
+        handler: &OSCQueryHandler,
+        params: &Option<HashMap<String, IdOrAlias>>,
+    ) -> Result<Option<HashMap<String, uuid::Uuid>>, CommandError> {
+        if let Some(params) = params {
+            let mut resolved = HashMap::new();
+            for (name, id) in params.iter() {
+                resolved.insert(name.clone(), self.resolve(handler, id)?);
+            }
+            Ok(Some(resolved))
+        } else {
+            Ok(None)
+        }
+    }
+
+    //get the item that owns the params
+    fn owner(
+        &self,
+        handler: &OSCQueryHandler,
+        owner: &ParamOwner,
+    ) -> Result<Arc<dyn ParamMapGet + Send + Sync>, CommandError> {
+        match owner {
+            ParamOwner::Param(id) => self
+                .param(handler, id)
+                .map(|p| p as _)
+                .ok_or(CommandError::ParamNotFound(*id)),
+            ParamOwner::GraphItem(id) => self
+                .graph_item(handler, id)
+                .map(|i| i as _)
+                .ok_or(CommandError::GraphItemNotFound(*id)),
+        }
+    }
+}
+
+impl OSCQueryHandler {
+    /// Validate all the `commands`, then apply them together, logging how to undo them.
+    pub(super) fn apply_atomic(&self, commands: &[Command], log: &mut ApplyLog) -> CommandResult {
+        let mut staged = Staged::default();
+        let mut batch = ApplyLog::default();
+        for (i, c) in commands.iter().enumerate() {
+            self.stage(c, &mut staged, &mut batch)
+                .map_err(|e| CommandError::Batch(vec![(i, e)]))?;
+        }
+        self.commit(staged)?;
+        log.undo.append(&mut batch.undo);
+        log.done.append(&mut batch.done);
+        Ok(())
+    }
+
+    //validate a command, creating and binding new items but leaving existing items alone
+    fn stage(&self, cmd: &Command, staged: &mut Staged, log: &mut ApplyLog) -> CommandResult {
+        match cmd {
+            Command::Batch(b) | Command::AtomicBatch(b) => {
+                for c in b.iter() {
+                    self.stage(c, staged, log)?;
+                }
+                Ok(())
+            }
+            Command::ParamCreate {
+                id,
+                type_name,
+                args,
+                params,
+            } => {
+                let id = id.unwrap_or_else(|| uuid::Uuid::new_v4());
+                if staged.param(self, &id).is_some() {
+                    return Err(CommandError::IdExists(id));
+                }
+                let params = staged.resolve_params(self, params)?;
+                let param = crate::param::factory::create_param(
+                    &id,
+                    type_name,
+                    args.clone(),
+                    &self.queue_sources,
+                )
+                .map_err(|error| CommandError::Create {
+                    id,
+                    type_name: type_name.clone(),
+                    error,
+                })?;
+                staged.params.insert(id, Arc::new(param));
+                //deleting also removes the bindings
+                log.undo.push(Command::ParamDelete { id: id.into() });
+                log.done.push(Command::ParamCreate {
+                    id: Some(id),
+                    type_name: type_name.clone(),
+                    args: args.clone(),
+                    params: resolved(&params),
+                });
+                self.stage_bind_all(staged, &ParamOwner::Param(id), &params)
+            }
+            Command::GraphItemCreate {
+                id,
+                type_name,
+                args,
+                children,
+                params,
+                autostart,
+            } => {
+                let id = id.unwrap_or_else(|| uuid::Uuid::new_v4());
+                if staged.graph_item(self, &id).is_some() {
+                    return Err(CommandError::IdExists(id));
+                }
+                let children = if let Some(children) = children {
+                    Some(staged.resolve_children(self, children)?)
+                } else {
+                    None
+                };
+                let params = staged.resolve_params(self, params)?;
+                let item = crate::graph::factory::create_instance(
+                    &id,
+                    type_name,
+                    args.clone(),
+                    &self.queue_sources,
+                )
+                .map_err(|error| CommandError::Create {
+                    id,
+                    type_name: type_name.clone(),
+                    error,
+                })?;
+                staged.graph.insert(id, Arc::new(item));
+                //deleting also removes the children and bindings
+                log.undo.push(Command::GraphItemDelete { id: id.into() });
+                log.done.push(Command::GraphItemCreate {
+                    id: Some(id),
+                    type_name: type_name.clone(),
+                    args: args.clone(),
+                    children: children.clone().map(|c| c.into()),
+                    params: resolved(&params),
+                    autostart: *autostart,
+                });
+                if autostart.unwrap_or(true) {
+                    staged.roots.push(id);
+                }
+                if let Some(children) = children {
+                    self.stage_children(staged, &id, &children)?;
+                }
+                self.stage_bind_all(staged, &ParamOwner::GraphItem(id), &params)
+            }
+            Command::ParamBind {
+                owner,
+                param_name,
+                param_id,
+            } => {
+                let owner = staged.resolve_owner(self, owner)?;
+                let param_id = staged.resolve(self, param_id)?;
+                let prev = self.stage_bind(staged, &owner, param_name, &param_id)?;
+                log.undo.push(rebind(&owner, param_name, prev));
+                log.done.push(rebind(&owner, param_name, Some(param_id)));
+                Ok(())
+            }
+            Command::ParamUnbind { owner, param_name } => {
+                let owner = staged.resolve_owner(self, owner)?;
+                if let Some(prev) = self.stage_unbind(staged, &owner, param_name)? {
+                    log.undo.push(rebind(&owner, param_name, Some(prev)));
+                    log.done.push(rebind(&owner, param_name, None));
+                }
+                Ok(())
+            }
+            Command::GraphNodeSetChildren {
+                parent_id,
+                children,
+            } => {
+                let parent_id = staged.resolve(self, parent_id)?;
+                let children = staged.resolve_children(self, children)?;
+                let prev = self.stage_children(staged, &parent_id, &children)?;
+                log.undo.push(Command::GraphNodeSetChildren {
+                    parent_id: parent_id.into(),
+                    children: prev.into(),
+                });
+                log.done.push(Command::GraphNodeSetChildren {
+                    parent_id: parent_id.into(),
+                    children: children.into(),
+                });
+                Ok(())
+            }
+            Command::AliasSet { name, id } => {
+                let id = staged.resolve(self, id)?;
+                let node = alias_node(name, &id)?;
+                if staged.param(self, &id).is_none() && staged.graph_item(self, &id).is_none() {
+                    return Err(CommandError::IdNotFound(id));
+                }
+                let prev = self.staged_alias(staged, name);
+                staged.aliases.push((name.clone(), Some((id, node))));
+                log.undo.push(if let Some(prev) = prev {
+                    Command::AliasSet {
+                        name: name.clone(),
+                        id: prev.into(),
+                    }
+                } else {
+                    Command::AliasRemove { name: name.clone() }
+                });
+                log.done.push(Command::AliasSet {
+                    name: name.clone(),
+                    id: id.into(),
+                });
+                Ok(())
+            }
+            Command::AliasRemove { name } => {
+                let prev = self
+                    .staged_alias(staged, name)
+                    .ok_or_else(|| CommandError::AliasNotFound(name.clone()))?;
+                staged.aliases.push((name.clone(), None));
+                log.undo.push(Command::AliasSet {
+                    name: name.clone(),
+                    id: prev.into(),
+                });
+                log.done.push(Command::AliasRemove { name: name.clone() });
+                Ok(())
+            }
+            _ => Err(CommandError::NotAllowedInAtomicBatch),
+        }
+    }
+
+    //the id the alias refers to, including changes made by the batch
+    fn staged_alias(&self, staged: &Staged, name: &str) -> Option<uuid::Uuid> {
+        staged.alias(name).unwrap_or_else(|| {
+            self.aliases
+                .lock()
+                .unwrap()
+                .get(name)
+                .map(|(id, _)| id.clone())
+        })
+    }
+
+    //returns the id of the param that was previously bound, if any
+    fn stage_bind(
+        &self,
+        staged: &mut Staged,
+        owner: &ParamOwner,
+        param_name: &str,
+        param_id: &uuid::Uuid,
+    ) -> Result<Option<uuid::Uuid>, CommandError> {
+        let param = staged
+            .param(self, param_id)
+            .ok_or(CommandError::ParamNotFound(*param_id))?;
+        //created params aren't in the live map, their bindings are in the batch edges
+        self.param_cycle_check(&self.params.lock().unwrap(), owner, param_id, &staged.edges)?;
+        let params = staged.owner(self, owner)?;
+        let binding_error = |error| CommandError::Binding {
+            owner: owner.uuid(),
+            param_name: param_name.to_string(),
+            param_id: Some(*param_id),
+            error,
+        };
+        if staged.is_staged(&owner.uuid()) {
+            //nothing else can see created items yet so they're bound right away
+            let prev = params.params().uuid(param_name);
+            params
+                .params()
+                .try_bind(param_name, param)
+                .map_err(binding_error)?;
+            staged.edges.bind(owner, param_id);
+            Ok(prev)
+        } else {
+            params
+                .params()
+                .can_bind(param_name, &param)
+                .map_err(binding_error)?;
+            let key = (owner.uuid(), param_name.to_string());
+            let prev = staged
+                .bindings
+                .get(&key)
+                .cloned()
+                .unwrap_or_else(|| params.params().uuid(param_name));
+            staged.bindings.insert(key, Some(*param_id));
+            staged.edges.bind(owner, param_id);
+            staged.pending.bind(owner, param_id);
+            staged.changes.push(Change::Bind {
+                owner: params,
+                name: param_name.to_string(),
+                param,
+                error: None,
+            });
+            Ok(prev)
+        }
+    }
+
+    //bind all the params, stopping at the first error
+    fn stage_bind_all(
+        &self,
+        staged: &mut Staged,
+        owner: &ParamOwner,
+        params: &Option<HashMap<String, uuid::Uuid>>,
+    ) -> CommandResult {
+        if let Some(params) = params {
+            for (name, id) in params.iter() {
+                self.stage_bind(staged, owner, name, id)?;
+            }
+        }
+        Ok(())
+    }
+
+    //returns the id of the param that was bound, if any
+    fn stage_unbind(
+        &self,
+        staged: &mut Staged,
+        owner: &ParamOwner,
+        param_name: &str,
+    ) -> Result<Option<uuid::Uuid>, CommandError> {
+        let params = staged.owner(self, owner)?;
+        if !params.params().contains_key(param_name) {
+            return Err(CommandError::Binding {
+                owner: owner.uuid(),
+                param_name: param_name.to_string(),
+                param_id: None,
+                error: BindingError::KeyMissing,
+            });
+        }
+        if staged.is_staged(&owner.uuid()) {
+            return Ok(params.params().unbind(param_name).map(|p| p.uuid()));
+        }
+        let key = (owner.uuid(), param_name.to_string());
+        let prev = staged
+            .bindings
+            .get(&key)
+            .cloned()
+            .unwrap_or_else(|| params.params().uuid(param_name));
+        if prev.is_some() {
+            staged.bindings.insert(key, None);
+            staged.pending.uses(&owner.uuid());
+            staged.changes.push(Change::Unbind {
+                owner: params,
+                name: param_name.to_string(),
+                unbound: None,
+            });
+        }
+        Ok(prev)
+    }
+
+    //returns the children that will be replaced
+    fn stage_children(
+        &self,
+        staged: &mut Staged,
+        parent_id: &uuid::Uuid,
+        children: &GraphNodeChildren,
+    ) -> Result<GraphNodeChildren, CommandError> {
+        let parent = staged
+            .graph_item(self, parent_id)
+            .ok_or(CommandError::GraphItemNotFound(*parent_id))?;
+        let current = graph_node_children(&parent).ok_or(CommandError::NoChildren(*parent_id))?;
+        let children = {
+            //created items aren't in the live map, their children are in the batch edges
+            let graph = self.graph.lock().unwrap();
+            let children = Self::graph_children(
+                &|id| staged.graph.get(id).or_else(|| graph.get(id)).cloned(),
+                children,
+            )
+            .map_err(CommandError::GraphItemNotFound)?;
+            self.graph_cycle_check(&graph, parent_id, &children.1, &staged.edges)?;
+            children
+        };
+        staged.edges.children(parent_id, &children.1);
+        if staged.is_staged(parent_id) {
+            //nothing else can see created items yet so their children are set right away
+            let old = parent
+                .children_swap(children)
+                .map_err(|_| CommandError::NoChildren(*parent_id))?;
+            self.dispose(old);
+            Ok(current)
+        } else {
+            let prev = staged
+                .children
+                .insert(*parent_id, (&children).into())
+                .unwrap_or(current);
+            staged.pending.children(parent_id, &children.1);
+            staged.changes.push(Change::ChildrenSwap {
+                item: parent,
+                children: Some(children),
+            });
+            Ok(prev)
+        }
+    }
+
+    //start the created roots and schedule the changes to existing items for the next tick, then
+    //add the created items and aliases
+    //
+    //everything that can fail has been done while staging, other than enqueuing the events which
+    //either all succeed or all fail
+    fn commit(&self, mut staged: Staged) -> CommandResult {
+        let tick = self.queue_sources.tick_next();
+        let mut changes = staged.changes;
+        let mut events = Vec::new();
+        let mut started = Vec::new();
+        for id in staged.roots.iter() {
+            if let Some((event, gate)) = staged.graph.get(id).and_then(|i| i.root_event_gate()) {
+                events.push((event, gate.clone()));
+                started.push(*id);
+                staged.pending.uses(id);
+                changes.push(Change::RootActivate {
+                    item: staged.graph[id].clone(),
+                    gate: Some(gate),
+                });
+            }
+        }
+        self.enqueue_changes(tick, changes, events)?;
+        self.pending().push(tick, staged.pending);
+        for (_, param) in staged.params {
+            self.add_param(param);
+        }
+        for (_, item) in staged.graph {
+            self.insert_graph_item(item);
+        }
+        for (name, alias) in staged.aliases {
+            if let Some((id, node)) = alias {
+                self.alias_insert(&name, &id, node);
+            } else {
+                self.alias_remove(&name);
+            }
+        }
+        let mut starts = self.root_starts.lock().unwrap();
+        for id in started {
+            starts.insert(id, tick);
+        }
+        Ok(())
+    }
+}
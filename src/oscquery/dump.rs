@@ -0,0 +1,169 @@
+//! Describe the current params, graph items and their bindings as json or graphviz dot.
+
+use crate::{
+    graph::GraphItem,
+    param::{Param, ParamHashMap, ParamMapGet},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, value::Value as JsonValue};
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+};
+
+pub(super) type ParamMap = Arc<Mutex<HashMap<uuid::Uuid, Arc<Param>>>>;
+pub(super) type GraphMap = Arc<Mutex<HashMap<uuid::Uuid, Arc<GraphItem>>>>;
+
+/// The output format of a dump.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub(super) enum DumpFormat {
+    Json,
+    Dot,
+}
+
+//wrapper to impl Get
+pub(super) struct Dump {
+    params: ParamMap,
+    graph: GraphMap,
+    format: DumpFormat,
+}
+
+fn slots(map: &ParamHashMap) -> JsonValue {
+    let mut slots = serde_json::Map::new();
+    for key in map.keys() {
+        slots.insert(
+            key.to_string(),
+            json!({
+                "access": map.access_name(key),
+                "data_type": map.data_type_name(key),
+                "id": map.uuid(key),
+            }),
+        );
+    }
+    JsonValue::Object(slots)
+}
+
+//sort by uuid so that the output is stable
+fn sorted<T>(map: &Mutex<HashMap<uuid::Uuid, Arc<T>>>) -> Vec<Arc<T>> {
+    let mut items: Vec<(uuid::Uuid, Arc<T>)> = map
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    items.sort_by_key(|(k, _)| k.clone());
+    items.into_iter().map(|(_, v)| v).collect()
+}
+
+/// Describe all the params and graph items as json.
+pub(super) fn json(params: &ParamMap, graph: &GraphMap) -> JsonValue {
+    let params: Vec<JsonValue> = sorted(params)
+        .iter()
+        .map(|p| {
+            json!({
+                "id": p.uuid(),
+                "type_name": p.type_name(),
+                "access": p.access_name(),
+                "data_type": p.data_type_name(),
+                "params": slots(p.params()),
+            })
+        })
+        .collect();
+    let graph: Vec<JsonValue> = sorted(graph)
+        .iter()
+        .map(|i| {
+            json!({
+                "id": i.uuid(),
+                "type_name": i.type_name(),
+                "kind": i.kind_name(),
+                "children_type_name": i.children_type_name(),
+                "children": i.children_uuids(),
+                "active": i.root_active(),
+                "params": slots(i.params()),
+            })
+        })
+        .collect();
+    json!({
+        "params": params,
+        "graph": graph,
+    })
+}
+
+//write an edge for each bound param slot
+fn binding_edges(out: &mut String, id: &uuid::Uuid, map: &ParamHashMap) {
+    for key in map.keys() {
+        if let Some(bound) = map.uuid(key) {
+            let _ = writeln!(
+                out,
+                "  \"{}\" -> \"{}\" [style=dashed, label=\"{}\"];",
+                bound, id, key
+            );
+        }
+    }
+}
+
+/// Describe all the params and graph items as a graphviz dot digraph.
+///
+/// Graph items are boxes, double for roots and rounded for leaves, params are ellipses.
+/// Children edges are solid and labeled with their index, param binding edges are dashed and
+/// labeled with the slot name.
+pub(super) fn dot(params: &ParamMap, graph: &GraphMap) -> String {
+    let mut out = String::from("digraph xsched {\n");
+    for i in sorted(graph).iter() {
+        let id = i.uuid();
+        let shape = match i.kind_name() {
+            "root" => "box, peripheries=2",
+            "leaf" => "box, style=rounded",
+            _ => "box",
+        };
+        let _ = writeln!(
+            out,
+            "  \"{}\" [shape={}, label=\"{}\\n{}\"];",
+            id,
+            shape,
+            i.type_name(),
+            id
+        );
+        if let Some(children) = i.children_uuids() {
+            for (index, child) in children.iter().enumerate() {
+                let _ = writeln!(out, "  \"{}\" -> \"{}\" [label=\"{}\"];", id, child, index);
+            }
+        }
+        binding_edges(&mut out, &id, i.params());
+    }
+    for p in sorted(params).iter() {
+        let id = p.uuid();
+        let _ = writeln!(
+            out,
+            "  \"{}\" [shape=ellipse, label=\"{}\\n{} {}\\n{}\"];",
+            id,
+            p.type_name(),
+            p.access_name(),
+            p.data_type_name(),
+            id
+        );
+        binding_edges(&mut out, &id, p.params());
+    }
+    out.push_str("}\n");
+    out
+}
+
+impl Dump {
+    pub(super) fn new(params: &ParamMap, graph: &GraphMap, format: DumpFormat) -> Self {
+        Self {
+            params: params.clone(),
+            graph: graph.clone(),
+            format,
+        }
+    }
+}
+
+impl ::oscquery::value::Get<String> for Dump {
+    fn get(&self) -> String {
+        match self.format {
+            DumpFormat::Json => json(&self.params, &self.graph).to_string(),
+            DumpFormat::Dot => dot(&self.params, &self.graph),
+        }
+    }
+}
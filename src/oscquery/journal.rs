@@ -0,0 +1,217 @@
+//! Record handled commands to a json lines journal, with the ticks they took effect at, and
+//! replay them at the same ticks.
+//!
+//! Creates are recorded with the ids they were given and scheduled commands with the absolute
+//! tick they were scheduled at, so later commands that refer to them replay correctly. Undo and
+//! redo are recorded as the commands they applied.
+//!
+//! # Remarks
+//! Bindings, children and root changes are replayed through the scheduler, so they land at
+//! exactly the recorded tick. Creates, deletes and aliases are handled when the server polls
+//! for commands after the scheduler passes the recorded tick, as they were when recorded.
+
+use super::{io_error, Command, CommandRequest, OSCQueryHandler, ScheduleAt};
+use crate::error::CommandError;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A command as it was handled, with the ids of any items it created.
+#[derive(Deserialize, Serialize)]
+pub(super) struct JournalEntry {
+    /// Milliseconds since the unix epoch when the command was handled.
+    time: u64,
+    /// The scheduler tick the command took effect at.
+    tick: usize,
+    request: CommandRequest,
+}
+
+impl JournalEntry {
+    //changes that can be scheduled, and commands that were scheduled at an absolute tick, are
+    //replayed as soon as they're reached, anything else waits until the scheduler has passed
+    //the tick it was recorded at
+    fn waits(&self, tick_next: usize) -> bool {
+        let command = &self.request.command;
+        !(command.schedulable() || command.at_tick()) && self.tick >= tick_next
+    }
+
+    //the request to handle to replay the entry
+    fn into_request(self) -> CommandRequest {
+        let CommandRequest {
+            request_id,
+            command,
+        } = self.request;
+        let command = if command.schedulable() {
+            Command::Scheduled {
+                at: ScheduleAt::Tick(self.tick),
+                command: Box::new(command),
+            }
+        } else {
+            command
+        };
+        CommandRequest {
+            request_id,
+            command,
+        }
+    }
+}
+
+impl Command {
+    //can the command be applied by the scheduler as a `Scheduled` command
+    fn schedulable(&self) -> bool {
+        match self {
+            Self::Batch(b) | Self::AtomicBatch(b) => b.iter().all(Self::schedulable),
+            Self::ParamBind { .. }
+            | Self::ParamUnbind { .. }
+            | Self::GraphNodeSetChildren { .. }
+            | Self::RootStart { at: None, .. }
+            | Self::RootStop { at: None, .. }
+            | Self::RootRestart { at: None, .. } => true,
+            _ => false,
+        }
+    }
+
+    //is the command scheduled at an absolute tick
+    fn at_tick(&self) -> bool {
+        match self {
+            Self::Scheduled {
+                at: ScheduleAt::Tick(_),
+                ..
+            }
+            | Self::RootStart {
+                at: Some(ScheduleAt::Tick(_)),
+                ..
+            }
+            | Self::RootStop {
+                at: Some(ScheduleAt::Tick(_)),
+                ..
+            }
+            | Self::RootRestart {
+                at: Some(ScheduleAt::Tick(_)),
+                ..
+            } => true,
+            _ => false,
+        }
+    }
+}
+
+impl OSCQueryHandler {
+    /// Append every command that is handled, along with the time and scheduler tick, to a
+    /// journal file at `path`.
+    pub fn journal_record<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<()> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        self.journal = Some(BufWriter::new(file));
+        Ok(())
+    }
+
+    /// Read the journal at `path` and replay each of its commands at the tick it was recorded
+    /// at.
+    ///
+    /// # Remarks
+    /// Ticks are absolute so the replay should begin with a fresh scheduler.
+    pub fn journal_replay<P: AsRef<Path>>(&mut self, path: P) -> Result<(), CommandError> {
+        let file = File::open(path).map_err(io_error)?;
+        let mut entries: Vec<JournalEntry> = self.replay.drain(..).collect();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(io_error)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            entries
+                .push(serde_json::from_str(&line).map_err(|e| CommandError::Parse(e.to_string()))?);
+        }
+        //stable so commands at the same tick keep their order
+        entries.sort_by_key(|e| e.tick);
+        self.replay = entries.into();
+        Ok(())
+    }
+
+    //replace relative schedule times with the ticks they resolve to now, so the command is
+    //journaled with the ticks it takes effect at
+    pub(super) fn resolve_ticks(&self, command: Command) -> Command {
+        let tick = |at: ScheduleAt| self.schedule_tick(&at).map(ScheduleAt::Tick).unwrap_or(at);
+        match command {
+            Command::Batch(b) => Command::Batch(Box::new(
+                b.into_iter().map(|c| self.resolve_ticks(c)).collect(),
+            )),
+            Command::AtomicBatch(b) => Command::AtomicBatch(Box::new(
+                b.into_iter().map(|c| self.resolve_ticks(c)).collect(),
+            )),
+            Command::Scheduled { at, command } => Command::Scheduled {
+                at: tick(at),
+                command,
+            },
+            Command::RootStart { id, at: Some(at) } => Command::RootStart {
+                id,
+                at: Some(tick(at)),
+            },
+            Command::RootStop { id, at: Some(at) } => Command::RootStop {
+                id,
+                at: Some(tick(at)),
+            },
+            Command::RootRestart { id, at: Some(at) } => Command::RootRestart {
+                id,
+                at: Some(tick(at)),
+            },
+            command => command,
+        }
+    }
+
+    //undo and redo act on the history, which doesn't hold the changes that replay through the
+    //scheduler, so they're journaled as the commands they're about to apply
+    pub(super) fn journaled(&self, command: &Command) -> Option<Command> {
+        if self.journal.is_none() {
+            return None;
+        }
+        match command {
+            Command::Undo => self.undo_history.with_last(|entry| {
+                Command::Batch(Box::new(entry.undo.iter().rev().cloned().collect()))
+            }),
+            Command::Redo => self
+                .redo_history
+                .with_last(|entry| Command::Batch(Box::new(entry.done.clone()))),
+            _ => None,
+        }
+    }
+
+    //write the request to the journal, if we're recording one, with the tick its changes take
+    //effect at
+    pub(super) fn journal_write(&mut self, request: CommandRequest) -> CommandRequest {
+        let tick = self.queue_sources.tick_next();
+        if let Some(journal) = self.journal.as_mut() {
+            let entry = JournalEntry {
+                time: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_millis() as u64),
+                tick,
+                request,
+            };
+            let written = serde_json::to_writer(&mut *journal, &entry)
+                .map_err(|e| e.to_string())
+                .and_then(|_| writeln!(journal).map_err(|e| e.to_string()))
+                .and_then(|_| journal.flush().map_err(|e| e.to_string()));
+            if let Err(e) = written {
+                eprintln!("failed to write to journal {}", e);
+            }
+            entry.request
+        } else {
+            request
+        }
+    }
+
+    //take the replay requests that can be handled now, in order, given the next tick the
+    //scheduler will run
+    pub(super) fn replay_due(&mut self, tick_next: usize) -> Vec<CommandRequest> {
+        let mut due = Vec::new();
+        while self.replay.front().map_or(false, |e| !e.waits(tick_next)) {
+            if let Some(entry) = self.replay.pop_front() {
+                due.push(entry.into_request());
+            }
+        }
+        due
+    }
+}
@@ -0,0 +1,130 @@
+//! Structural changes that have been validated but not yet applied in the scheduler thread.
+//!
+//! Cycle checks include the dependencies that pending changes will add, so changes that are
+//! each fine on their own can't combine into a cycle once they have all been applied.
+
+use super::ParamOwner;
+
+/// The dependencies added by, and the items used by, changes that haven't been applied.
+#[derive(Default)]
+pub(super) struct Edges {
+    //(param, the param it will be bound to)
+    params: Vec<(uuid::Uuid, uuid::Uuid)>,
+    //(parent, a child it will have)
+    children: Vec<(uuid::Uuid, uuid::Uuid)>,
+    //every item the changes use
+    ids: Vec<uuid::Uuid>,
+}
+
+/// The edges of scheduled changes along with the tick they will be applied at.
+#[derive(Default)]
+pub(super) struct Pending(Vec<(usize, Edges)>);
+
+impl Edges {
+    /// Record binding `param_id` to `owner`.
+    pub fn bind(&mut self, owner: &ParamOwner, param_id: &uuid::Uuid) {
+        if let ParamOwner::Param(owner_id) = owner {
+            self.params.push((*owner_id, *param_id));
+        }
+        self.ids.push(owner.uuid());
+        self.ids.push(*param_id);
+    }
+
+    /// Record giving `parent_id` the `children_ids`.
+    pub fn children(&mut self, parent_id: &uuid::Uuid, children_ids: &[uuid::Uuid]) {
+        self.children
+            .extend(children_ids.iter().map(|child_id| (*parent_id, *child_id)));
+        self.ids.push(*parent_id);
+        self.ids.extend(children_ids.iter().cloned());
+    }
+
+    /// Record a change that uses `id` without adding any dependencies.
+    pub fn uses(&mut self, id: &uuid::Uuid) {
+        self.ids.push(*id);
+    }
+
+    /// The params that `id` will be bound to.
+    pub fn param_deps<'a>(&'a self, id: &'a uuid::Uuid) -> impl Iterator<Item = uuid::Uuid> + 'a {
+        self.params
+            .iter()
+            .filter(move |(from, _)| from == id)
+            .map(|(_, to)| *to)
+    }
+
+    /// The children that `id` will have.
+    pub fn child_deps<'a>(&'a self, id: &'a uuid::Uuid) -> impl Iterator<Item = uuid::Uuid> + 'a {
+        self.children
+            .iter()
+            .filter(move |(from, _)| from == id)
+            .map(|(_, to)| *to)
+    }
+
+    pub fn uses_id(&self, id: &uuid::Uuid) -> bool {
+        self.ids.contains(id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+impl Pending {
+    /// Forget the changes that have been applied, given the next tick the scheduler will process.
+    pub fn prune(&mut self, tick_next: usize) {
+        self.0.retain(|(tick, _)| *tick >= tick_next);
+    }
+
+    pub fn push(&mut self, tick: usize, edges: Edges) {
+        if !edges.is_empty() {
+            self.0.push((tick, edges));
+        }
+    }
+
+    /// The params that `id` will be bound to by pending changes.
+    pub fn param_deps(&self, id: &uuid::Uuid) -> Vec<uuid::Uuid> {
+        self.0.iter().flat_map(|(_, e)| e.param_deps(id)).collect()
+    }
+
+    /// The children that `id` will have after pending changes.
+    pub fn child_deps(&self, id: &uuid::Uuid) -> Vec<uuid::Uuid> {
+        self.0.iter().flat_map(|(_, e)| e.child_deps(id)).collect()
+    }
+
+    /// The tick of the first pending change that uses `id`, if any.
+    pub fn uses_id(&self, id: &uuid::Uuid) -> Option<usize> {
+        self.0
+            .iter()
+            .filter(|(_, e)| e.uses_id(id))
+            .map(|(tick, _)| *tick)
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending() {
+        let a = uuid::Uuid::new_v4();
+        let b = uuid::Uuid::new_v4();
+        let c = uuid::Uuid::new_v4();
+        let mut edges = Edges::default();
+        edges.bind(&ParamOwner::Param(a), &b);
+        edges.children(&c, &[a]);
+        assert_eq!(vec![b], edges.param_deps(&a).collect::<Vec<_>>());
+        assert_eq!(vec![a], edges.child_deps(&c).collect::<Vec<_>>());
+
+        let mut pending = Pending::default();
+        pending.push(10, edges);
+        pending.push(5, Edges::default());
+        assert_eq!(Some(10), pending.uses_id(&b));
+        assert_eq!(vec![b], pending.param_deps(&a));
+        assert!(pending.param_deps(&b).is_empty());
+        pending.prune(10);
+        assert_eq!(Some(10), pending.uses_id(&c));
+        pending.prune(11);
+        assert_eq!(None, pending.uses_id(&c));
+        assert!(pending.child_deps(&c).is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+//! Save and load params, graph items and aliases as a versioned json document.
+
+use super::{
+    graph_node_children, io_error, ApplyLog, Command, CommandResult, GraphNodeChildren,
+    OSCQueryHandler, ParamOwner,
+};
+use crate::{
+    error::CommandError,
+    param::{ParamHashMap, ParamMapGet},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::value::Value as JsonValue;
+use std::{collections::HashMap, path::Path};
+
+/// The version of the session document that we write and can read.
+pub const SESSION_VERSION: usize = 1;
+
+#[derive(Deserialize, Serialize)]
+struct Session {
+    version: usize,
+    params: Vec<SessionParam>,
+    graph: Vec<SessionGraphItem>,
+    aliases: HashMap<String, uuid::Uuid>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct SessionParam {
+    id: uuid::Uuid,
+    type_name: String,
+    args: Option<JsonValue>,
+    //the current shadow value, if it can be read
+    value: Option<JsonValue>,
+    params: HashMap<String, uuid::Uuid>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct SessionGraphItem {
+    id: uuid::Uuid,
+    type_name: String,
+    args: Option<JsonValue>,
+    children: Option<GraphNodeChildren>,
+    params: HashMap<String, uuid::Uuid>,
+    active: bool,
+}
+
+//get the bound param ids by param name
+fn bindings(map: &ParamHashMap) -> HashMap<String, uuid::Uuid> {
+    map.keys()
+        .filter_map(|key| map.uuid(key).map(|id| (key.to_string(), id)))
+        .collect()
+}
+
+impl OSCQueryHandler {
+    fn session(&self) -> Session {
+        let params = self
+            .params
+            .lock()
+            .unwrap()
+            .values()
+            .map(|p| SessionParam {
+                id: p.uuid(),
+                type_name: p.type_name().to_string(),
+                args: p.args().cloned(),
+                value: p.shadow().as_ref().and_then(|s| s.value_json()),
+                params: bindings(p.params()),
+            })
+            .collect();
+        let graph = self
+            .graph
+            .lock()
+            .unwrap()
+            .values()
+            .map(|i| SessionGraphItem {
+                id: i.uuid(),
+                type_name: i.type_name().to_string(),
+                args: i.args().cloned(),
+                children: graph_node_children(i),
+                params: bindings(i.params()),
+                active: i.root_active().unwrap_or(false),
+            })
+            .collect();
+        let aliases = self
+            .aliases
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, (id, _))| (name.clone(), id.clone()))
+            .collect();
+        Session {
+            version: SESSION_VERSION,
+            params,
+            graph,
+            aliases,
+        }
+    }
+
+    /// Save all the params, graph items and aliases to a json file at `path`.
+    pub fn session_save<P: AsRef<Path>>(&self, path: P) -> CommandResult {
+        let file = std::fs::File::create(path).map_err(io_error)?;
+        serde_json::to_writer_pretty(std::io::BufWriter::new(file), &self.session())
+            .map_err(|e| CommandError::Io(e.to_string()))
+    }
+
+    /// Load params, graph items and aliases from a json file at `path`, adding them to what
+    /// already exists.
+    ///
+    /// # Remarks
+    /// * Nothing is loaded if any item fails to be created or bound.
+    /// * Roots that were active when the session was saved are started.
+    pub fn session_load<P: AsRef<Path>>(&self, path: P) -> CommandResult {
+        let file = std::fs::File::open(path).map_err(io_error)?;
+        let session: Session = serde_json::from_reader(std::io::BufReader::new(file))
+            .map_err(|e| CommandError::Parse(e.to_string()))?;
+        if session.version != SESSION_VERSION {
+            return Err(CommandError::SessionVersion(session.version));
+        }
+
+        //create everything before binding so the order in the document doesn't matter
+        let mut commands = Vec::new();
+        for p in session.params.iter() {
+            commands.push(Command::ParamCreate {
+                id: Some(p.id),
+                type_name: p.type_name.clone(),
+                args: p.args.clone().unwrap_or(JsonValue::Null),
+                params: None,
+            });
+        }
+        for g in session.graph.iter() {
+            commands.push(Command::GraphItemCreate {
+                id: Some(g.id),
+                type_name: g.type_name.clone(),
+                args: g.args.clone(),
+                children: None,
+                params: None,
+                autostart: Some(false),
+            });
+        }
+        for p in session.params.iter() {
+            for (name, id) in p.params.iter() {
+                commands.push(Command::ParamBind {
+                    owner: ParamOwner::Param(p.id.into()),
+                    param_name: name.clone(),
+                    param_id: id.clone().into(),
+                });
+            }
+        }
+        for g in session.graph.iter() {
+            for (name, id) in g.params.iter() {
+                commands.push(Command::ParamBind {
+                    owner: ParamOwner::GraphItem(g.id.into()),
+                    param_name: name.clone(),
+                    param_id: id.clone().into(),
+                });
+            }
+            if let Some(children) = &g.children {
+                commands.push(Command::GraphNodeSetChildren {
+                    parent_id: g.id.into(),
+                    children: children.clone().into(),
+                });
+            }
+        }
+        for (name, id) in session.aliases.iter() {
+            commands.push(Command::AliasSet {
+                name: name.clone(),
+                id: id.clone().into(),
+            });
+        }
+        let mut log = ApplyLog::default();
+        self.apply(&Command::AtomicBatch(Box::new(commands)), &mut log)?;
+
+        //restore the values then start the roots
+        {
+            let params = self.params.lock().unwrap();
+            for p in session.params.iter() {
+                if let (Some(value), Some(param)) = (&p.value, params.get(&p.id)) {
+                    if let Some(shadow) = param.shadow() {
+                        let _ = shadow.set_json(value.clone());
+                    }
+                }
+            }
+        }
+        let roots: Vec<uuid::Uuid> = session
+            .graph
+            .iter()
+            .filter(|g| g.active)
+            .map(|g| g.id)
+            .collect();
+        self.start_roots(&roots);
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use sched::{
     binding::swap::{BindingSwapGet, BindingSwapSet},
     mutex::Mutex,
 };
+use serde::Serialize;
 use std::{
     collections::{hash_map::Keys, HashMap},
     convert::TryInto,
@@ -14,7 +15,15 @@ use sched::binding::{
     ParamBindingGet, ParamBindingKeyValueGet, ParamBindingKeyValueSet, ParamBindingSet,
 };
 
+pub mod array;
+pub mod euclid;
 pub mod factory;
+pub mod modulation;
+pub mod ops;
+pub mod queued;
+pub mod random;
+pub mod scale;
+pub mod string;
 
 pub type ParamBindingGetSet<T> =
     ::sched::binding::ParamBindingGetSet<T, Arc<dyn ::sched::binding::ParamBinding<T>>>;
@@ -53,7 +62,7 @@ pub enum ParamAccess {
 }
 
 /// Errors in binding parameters.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum BindingError {
     /// No parameter with the given name
     KeyMissing,
@@ -77,6 +86,8 @@ pub struct Param {
     params: ParamHashMap,
     uuid: uuid::Uuid,
     type_name: &'static str,
+    //the arguments this param was created with, if it was created by a factory
+    args: Option<serde_json::Value>,
 }
 
 impl ParamHashMap {
@@ -122,6 +133,11 @@ impl ParamHashMap {
         }
     }
 
+    /// Get the uuids of all the bound parameters.
+    pub fn uuids(&self) -> Vec<uuid::Uuid> {
+        self.inner.values().filter_map(|p| p.uuid()).collect()
+    }
+
     pub fn unbind(&self, name: &str) -> Option<Arc<Param>> {
         if let Some(param) = self.inner.get(name) {
             match param {
@@ -150,6 +166,29 @@ impl ParamHashMap {
         }
     }
 
+    ///See if the parameter with the given `name` could be bound to `binding`, without binding.
+    pub fn can_bind(&self, name: &str, binding: &Param) -> Result<(), BindingError> {
+        if let Some(param) = self.inner.get(name) {
+            let data_type_matches = param.data_type_name() == binding.data_type_name();
+            match (param, binding.access_name()) {
+                (ParamAccess::Get { .. }, "get") | (ParamAccess::Get { .. }, "getset")
+                    if data_type_matches =>
+                {
+                    Ok(())
+                }
+                (ParamAccess::Set { .. }, "set") | (ParamAccess::Set { .. }, "getset")
+                    if data_type_matches =>
+                {
+                    Ok(())
+                }
+                (ParamAccess::Get { .. }, _) => Err(BindingError::NoGet),
+                (ParamAccess::Set { .. }, _) => Err(BindingError::NoSet),
+            }
+        } else {
+            Err(BindingError::KeyMissing)
+        }
+    }
+
     ///Insert a parameter into the mapping, it should be unbound.
     pub(crate) fn insert_unbound(&mut self, name: &'static str, param: ParamAccess) {
         assert!(!self.inner.contains_key(name));
@@ -215,9 +254,16 @@ impl Param {
             params: params.into(),
             uuid: id.clone(),
             type_name,
+            args: None,
         }
     }
 
+    /// Record the arguments that this param was created with.
+    pub fn with_args(mut self, args: serde_json::Value) -> Self {
+        self.args = Some(args);
+        self
+    }
+
     /// Get the unique identifier for this param instance.
     pub fn uuid(&self) -> uuid::Uuid {
         self.uuid
@@ -243,6 +289,11 @@ impl Param {
         self.data.data_type_name()
     }
 
+    /// Get the arguments this param was created with, if they were recorded.
+    pub fn args(&self) -> Option<&serde_json::Value> {
+        self.args.as_ref()
+    }
+
     /// Get the shadow for this param, if there is one.
     pub fn shadow(&self) -> &Option<ParamDataAccess> {
         &self.shadow
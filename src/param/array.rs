@@ -0,0 +1,267 @@
+//! Array valued params: storage, a swappable binding for array param slots and indexing.
+//!
+//! Maps are arrays with a name for each index, the names are sorted so a value's index is the
+//! position of its name in sorted order, whatever the order of the json object they were created
+//! from. Maps don't hold their names, a lookup is created with the names of the maps it reads.
+
+use crate::error::CreateError;
+use sched::{
+    atomic::Atomic,
+    binding::{
+        swap::BindingSwapGet, ParamBindingGet, ParamBindingKeyValueGet, ParamBindingKeyValueSet,
+        ParamBindingSet,
+    },
+    mutex::Mutex,
+};
+use serde::de::DeserializeOwned;
+use serde_json::value::Value as JsonValue;
+use std::{collections::HashMap, sync::Arc};
+
+/// A fixed length array of constant values.
+pub struct ArrayConst<T>(Vec<T>);
+
+/// A fixed length array of values that can be read and written from any thread.
+pub struct ArrayAtomic<T>(Vec<Atomic<T>>);
+
+/// A binding for array param slots that can be swapped out, reads as empty when unbound.
+pub struct BindingSwapKeyValueGet<T> {
+    binding: Mutex<Option<Arc<dyn ParamBindingKeyValueGet<T>>>>,
+}
+
+/// Get the value at `index` in `array`, or the default value if `index` is out of range.
+pub struct IndexGet<T> {
+    array: Arc<BindingSwapKeyValueGet<T>>,
+    index: Arc<BindingSwapGet<usize>>,
+}
+
+/// Get the value for `key` in `map`, or the default value if the map doesn't have the key.
+pub struct LookupGet<T> {
+    map: Arc<BindingSwapKeyValueGet<T>>,
+    key: Arc<BindingSwapGet<Arc<String>>>,
+    indexes: HashMap<String, usize>,
+}
+
+/// Get the values of a map from a json object, in key order.
+///
+/// Keys must be usable as OSC address parts.
+pub fn map_values<T: DeserializeOwned>(args: JsonValue) -> Result<Vec<T>, CreateError> {
+    if let JsonValue::Object(map) = args {
+        map.into_iter()
+            .map(|(key, value)| {
+                if !crate::oscquery::valid_address_part(&key) {
+                    Err(CreateError::InvalidArgs(format!("invalid key {:?}", key)))
+                } else {
+                    serde_json::from_value(value).map_err(|e| {
+                        CreateError::InvalidArgs(format!("invalid value for key {}: {}", key, e))
+                    })
+                }
+            })
+            .collect()
+    } else {
+        Err(CreateError::InvalidArgs("expected an object".into()))
+    }
+}
+
+/// Get the keys of a map from a json array of them or the json object the map was created from.
+pub fn map_keys(args: &JsonValue) -> Result<Vec<String>, CreateError> {
+    match args {
+        JsonValue::Object(map) => Ok(map.keys().cloned().collect()),
+        JsonValue::Array(_) => serde_json::from_value(args.clone())
+            .map_err(|e| CreateError::InvalidArgs(format!("invalid keys: {}", e))),
+        v => Err(CreateError::InvalidArgs(format!(
+            "expected an array or object of keys, got {}",
+            v
+        ))),
+    }
+}
+
+impl<T> ArrayConst<T> {
+    pub fn new(values: Vec<T>) -> Self {
+        Self(values)
+    }
+}
+
+impl<T> ArrayAtomic<T> {
+    pub fn new(values: Vec<T>) -> Self {
+        Self(values.into_iter().map(Atomic::new).collect())
+    }
+}
+
+impl<T> BindingSwapKeyValueGet<T> {
+    /// Bind to `binding`, replacing any existing binding.
+    pub fn bind(&self, binding: Arc<dyn ParamBindingKeyValueGet<T>>) {
+        self.binding.lock().replace(binding);
+    }
+
+    /// Remove the binding.
+    pub fn unbind(&self) {
+        self.binding.lock().take();
+    }
+}
+
+impl<T> IndexGet<T> {
+    pub fn new(array: Arc<BindingSwapKeyValueGet<T>>, index: Arc<BindingSwapGet<usize>>) -> Self {
+        Self { array, index }
+    }
+}
+
+impl<T> LookupGet<T> {
+    /// Look up `key` in maps with the keys `keys`.
+    pub fn new(
+        map: Arc<BindingSwapKeyValueGet<T>>,
+        key: Arc<BindingSwapGet<Arc<String>>>,
+        mut keys: Vec<String>,
+    ) -> Self {
+        keys.sort();
+        keys.dedup();
+        Self {
+            map,
+            key,
+            indexes: keys.into_iter().enumerate().map(|(i, k)| (k, i)).collect(),
+        }
+    }
+}
+
+impl<T> Default for BindingSwapKeyValueGet<T> {
+    fn default() -> Self {
+        Self {
+            binding: Default::default(),
+        }
+    }
+}
+
+impl<T> ParamBindingKeyValueGet<T> for ArrayConst<T>
+where
+    T: Copy + Send + Sync,
+{
+    fn get_at(&self, index: usize) -> Option<T> {
+        self.0.get(index).copied()
+    }
+
+    fn len(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+impl<T> ParamBindingKeyValueGet<T> for ArrayAtomic<T>
+where
+    T: Copy + Send + Sync,
+    Atomic<T>: ParamBindingGet<T>,
+{
+    fn get_at(&self, index: usize) -> Option<T> {
+        self.0.get(index).map(|v| v.get())
+    }
+
+    fn len(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+impl<T> ParamBindingKeyValueSet<T> for ArrayAtomic<T>
+where
+    T: Copy + Send + Sync,
+    Atomic<T>: ParamBindingSet<T>,
+{
+    //out of range indexes are ignored
+    fn set_at(&self, index: usize, value: T) {
+        if let Some(v) = self.0.get(index) {
+            v.set(value);
+        }
+    }
+}
+
+impl<T> ParamBindingKeyValueGet<T> for BindingSwapKeyValueGet<T>
+where
+    T: Copy + Send + Sync,
+{
+    fn get_at(&self, index: usize) -> Option<T> {
+        self.binding.lock().as_ref().and_then(|b| b.get_at(index))
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.binding.lock().as_ref().map_or(Some(0), |b| b.len())
+    }
+}
+
+impl<T> ParamBindingGet<T> for IndexGet<T>
+where
+    T: Copy + Default + Send + Sync,
+{
+    fn get(&self) -> T {
+        self.array.get_at(self.index.get()).unwrap_or_default()
+    }
+}
+
+impl<T> ParamBindingGet<T> for LookupGet<T>
+where
+    T: Copy + Default + Send + Sync,
+{
+    fn get(&self) -> T {
+        self.indexes
+            .get(self.key.get().as_str())
+            .and_then(|index| self.map.get_at(*index))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index() {
+        let array: Arc<BindingSwapKeyValueGet<u8>> = Default::default();
+        let index: Arc<BindingSwapGet<usize>> = Arc::new(BindingSwapGet::new(1));
+        let get = IndexGet::new(array.clone(), index.clone());
+        assert_eq!(0, get.get());
+
+        let values = Arc::new(ArrayAtomic::new(vec![1u8, 2, 3]));
+        array.bind(values.clone() as Arc<dyn ParamBindingKeyValueGet<u8>>);
+        assert_eq!(Some(3), array.len());
+        assert_eq!(2, get.get());
+        values.set_at(1, 20);
+        assert_eq!(20, get.get());
+        values.set_at(3, 40);
+        assert_eq!(None, array.get_at(3));
+
+        index.bind(Arc::new(5usize) as Arc<dyn ParamBindingGet<usize>>);
+        assert_eq!(0, get.get());
+        array.unbind();
+        assert_eq!(Some(0), array.len());
+    }
+
+    #[test]
+    fn map() {
+        let v: Vec<u8> = map_values(serde_json::json!({"snare": 38, "kick": 36})).unwrap();
+        assert_eq!(vec![36, 38], v);
+        assert!(map_values::<u8>(serde_json::json!({"kick": 300})).is_err());
+        assert!(map_values::<u8>(serde_json::json!({"kick/2": 36})).is_err());
+        assert!(map_values::<u8>(serde_json::json!([36])).is_err());
+    }
+
+    #[test]
+    fn lookup() {
+        let map: Arc<BindingSwapKeyValueGet<u8>> = Default::default();
+        let key: Arc<BindingSwapGet<Arc<String>>> =
+            Arc::new(BindingSwapGet::new(Arc::new("snare".to_string())));
+        let keys = map_keys(&serde_json::json!(["snare", "kick"])).unwrap();
+        let get = LookupGet::new(map.clone(), key.clone(), keys);
+        assert_eq!(0, get.get());
+
+        let args = serde_json::json!({"snare": 38, "kick": 36});
+        assert_eq!(vec!["kick", "snare"], map_keys(&args).unwrap());
+        let values: Vec<u8> = map_values(args).unwrap();
+        map.bind(Arc::new(ArrayAtomic::new(values)) as Arc<dyn ParamBindingKeyValueGet<u8>>);
+        assert_eq!(38, get.get());
+
+        let bind = |k: &str| {
+            let value = Arc::new(BindingSwapGet::new(Arc::new(k.to_string())));
+            key.bind(value as Arc<dyn ParamBindingGet<Arc<String>>>);
+        };
+        bind("kick");
+        assert_eq!(36, get.get());
+        bind("hat");
+        assert_eq!(0, get.get());
+        assert!(map_keys(&serde_json::json!("kick")).is_err());
+    }
+}
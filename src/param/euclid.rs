@@ -0,0 +1,111 @@
+//! Euclidean rhythms: pulses distributed as evenly as possible over steps.
+
+use sched::binding::{swap::BindingSwapGet, ParamBindingGet};
+use std::sync::Arc;
+
+/// Is there a pulse at step `index` of the euclidean rhythm of `pulses` in `steps`, delayed by
+/// `rotation` steps.
+///
+/// The index wraps around the steps, so it can be a counter.
+pub struct EuclidGet {
+    steps: Arc<BindingSwapGet<usize>>,
+    pulses: Arc<BindingSwapGet<usize>>,
+    rotation: Arc<BindingSwapGet<usize>>,
+    index: Arc<BindingSwapGet<usize>>,
+}
+
+/// Is step `index` of `pulses` distributed over `steps` a pulse, the first step is always a pulse
+/// if there are any.
+///
+/// Computed directly for the step, so reading it doesn't allocate.
+pub fn pulse(steps: usize, pulses: usize, index: usize) -> bool {
+    if steps == 0 {
+        false
+    } else if pulses >= steps {
+        true
+    } else {
+        //wide so that large step counts can't overflow
+        (index % steps) as u128 * pulses as u128 % (steps as u128) < pulses as u128
+    }
+}
+
+impl EuclidGet {
+    pub fn new(
+        steps: Arc<BindingSwapGet<usize>>,
+        pulses: Arc<BindingSwapGet<usize>>,
+        rotation: Arc<BindingSwapGet<usize>>,
+        index: Arc<BindingSwapGet<usize>>,
+    ) -> Self {
+        Self {
+            steps,
+            pulses,
+            rotation,
+            index,
+        }
+    }
+}
+
+impl ParamBindingGet<bool> for EuclidGet {
+    fn get(&self) -> bool {
+        let steps = self.steps.get();
+        if steps == 0 {
+            return false;
+        }
+        let rotation = self.rotation.get() % steps;
+        pulse(
+            steps,
+            self.pulses.get(),
+            self.index.get() % steps + steps - rotation,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(steps: usize, pulses: usize) -> String {
+        (0..steps)
+            .map(|i| if pulse(steps, pulses, i) { 'x' } else { '.' })
+            .collect()
+    }
+
+    #[test]
+    fn distribution() {
+        assert_eq!("x..x..x.", pattern(8, 3));
+        assert_eq!("x.x.xx.x", pattern(8, 5));
+        assert_eq!("x.x.x.x.", pattern(8, 4));
+        assert_eq!("x.x.x", pattern(5, 3));
+        assert_eq!("x..x..x.x..x..x.", pattern(16, 6));
+        assert_eq!("....", pattern(4, 0));
+        assert_eq!("xxx", pattern(3, 5));
+        assert_eq!("", pattern(0, 0));
+    }
+
+    #[test]
+    fn get() {
+        let index = Arc::new(BindingSwapGet::new(0usize));
+        let rotation = Arc::new(BindingSwapGet::new(0usize));
+        let e = EuclidGet::new(
+            Arc::new(BindingSwapGet::new(8)),
+            Arc::new(BindingSwapGet::new(3)),
+            rotation.clone(),
+            index.clone(),
+        );
+        let run = || -> String {
+            (0..8)
+                .map(|i| {
+                    index.bind(Arc::new(i + 8) as Arc<dyn ParamBindingGet<usize>>);
+                    if e.get() {
+                        'x'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        };
+        assert_eq!("x..x..x.", run());
+        rotation.bind(Arc::new(1usize) as Arc<dyn ParamBindingGet<usize>>);
+        assert_eq!(".x..x..x", run());
+    }
+}
@@ -1,10 +1,28 @@
 use crate::{
     error::CreateError,
-    param::{Param, ParamDataAccess, ParamHashMap},
+    param::{
+        array::{
+            map_keys, map_values, ArrayAtomic, ArrayConst, BindingSwapKeyValueGet, IndexGet,
+            LookupGet,
+        },
+        euclid::EuclidGet,
+        modulation::{Envelope, Lfo, Shape},
+        ops::{self, Arith, BinaryOp, SlotArgs, TernaryOp, UnaryOp},
+        queued::QueuedValue,
+        random::{self, ChoiceGet, RandomGet},
+        scale::{Scale, ScaleQuantize, ScaleSnap},
+        string::StringValue,
+        Param, ParamAccess, ParamDataAccess, ParamGet, ParamHashMap,
+    },
+    sched::QueueSource,
 };
 use sched::{
     atomic::Atomic,
-    binding::{ParamBinding, ParamBindingGet},
+    binding::{
+        swap::BindingSwapGet, ParamBinding, ParamBindingGet, ParamBindingKeyValue,
+        ParamBindingKeyValueGet,
+    },
+    Float,
 };
 use serde::Serialize;
 use serde_json::value::Value as JsonValue;
@@ -15,7 +33,7 @@ pub type ParamDataResult =
     Result<(ParamDataAccess, Option<ParamDataAccess>, ParamHashMap), CreateError>;
 
 /// Param Factory Function type.
-pub type ParamDataFn = dyn Fn(JsonValue) -> ParamDataResult + Sync;
+pub type ParamDataFn = dyn Fn(JsonValue, &Arc<dyn QueueSource>) -> ParamDataResult + Sync;
 
 /// Param Factory Item.
 #[derive(Serialize)] //just for display
@@ -41,8 +59,8 @@ impl ParamFactItem {
         }
     }
 
-    pub fn create(&self, args: JsonValue) -> ParamDataResult {
-        (self.func)(args)
+    pub fn create(&self, args: JsonValue, queue_sources: &Arc<dyn QueueSource>) -> ParamDataResult {
+        (self.func)(args, queue_sources)
     }
 
     /// Get the description.
@@ -60,10 +78,13 @@ pub fn create_param(
     uuid: &uuid::Uuid,
     type_name: &str,
     args: JsonValue,
+    queue_sources: &Arc<dyn QueueSource>,
 ) -> Result<Param, CreateError> {
     if let Some((key, f)) = PARAM_FACTORY_HASH.get_key_value(type_name) {
-        match f.create(args) {
-            Ok((access, shadow, map)) => Ok(Param::new_with_id(key, access, map, shadow, uuid)),
+        match f.create(args.clone(), queue_sources) {
+            Ok((access, shadow, map)) => {
+                Ok(Param::new_with_id(key, access, map, shadow, uuid).with_args(args))
+            }
             Err(e) => Err(e),
         }
     } else {
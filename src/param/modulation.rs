@@ -0,0 +1,355 @@
+//! Modulation sources whose values depend on the scheduler time: LFOs and envelopes.
+//!
+//! Roots and children are evaluated inside `with_eval_tick`, with the tick from their
+//! `EventEvalContext`, and params read the tick while they're evaluated. The tick is per thread
+//! and only set during evaluation, so reads from other threads, like OSCQuery, see the value at
+//! the last tick the scheduler evaluated and don't change any state.
+
+use crate::param::{ops::Cast, random};
+use sched::{
+    binding::{swap::BindingSwapGet, ParamBindingGet},
+    mutex::Mutex,
+    Float,
+};
+use std::{
+    cell::Cell,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+thread_local! {
+    static EVAL_TICK: Cell<Option<usize>> = Cell::new(None);
+}
+
+/// LFO wave shapes, all ranging from -1 to 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    Sine,
+    Triangle,
+    /// Rising from -1 to 1.
+    Saw,
+    Square,
+    /// A random value, held for each period.
+    SampleHold,
+}
+
+/// A low frequency oscillator: `offset + depth * shape(tick / period_ticks + phase)`.
+///
+/// The phase is in periods, `offset` is the value when `period_ticks` is zero.
+pub struct Lfo {
+    shape: Shape,
+    period_ticks: Arc<BindingSwapGet<usize>>,
+    phase: Arc<BindingSwapGet<Float>>,
+    depth: Arc<BindingSwapGet<Float>>,
+    offset: Arc<BindingSwapGet<Float>>,
+    seed: Arc<BindingSwapGet<usize>>,
+    //the last tick it was evaluated at
+    tick: AtomicUsize,
+}
+
+/// An ADSR envelope, `offset + depth * level`, the level ranges from 0 to 1.
+///
+/// When `trigger` becomes true the level ramps from its current value to 1 over `attack_ticks`,
+/// then to `sustain` over `decay_ticks`. When `trigger` becomes false it ramps to 0 over
+/// `release_ticks`. Trigger changes are detected when the value is read by the scheduler.
+pub struct Envelope {
+    trigger: Arc<BindingSwapGet<bool>>,
+    attack_ticks: Arc<BindingSwapGet<usize>>,
+    decay_ticks: Arc<BindingSwapGet<usize>>,
+    sustain: Arc<BindingSwapGet<Float>>,
+    release_ticks: Arc<BindingSwapGet<usize>>,
+    depth: Arc<BindingSwapGet<Float>>,
+    offset: Arc<BindingSwapGet<Float>>,
+    state: Mutex<EnvelopeState>,
+}
+
+#[derive(Default)]
+struct EnvelopeState {
+    gate: bool,
+    start: usize,
+    from: f64,
+    level: f64,
+}
+
+/// Call `f` with `tick` as the tick the scheduler is evaluating, called from the scheduler
+/// thread with the tick from the `EventEvalContext`.
+pub fn with_eval_tick<R>(tick: usize, f: impl FnOnce() -> R) -> R {
+    let prev = EVAL_TICK.with(|t| t.replace(Some(tick)));
+    let r = f();
+    EVAL_TICK.with(|t| t.set(prev));
+    r
+}
+
+/// The tick that the scheduler running in this thread is evaluating, `None` outside of
+/// evaluation.
+pub fn eval_tick() -> Option<usize> {
+    EVAL_TICK.with(|t| t.get())
+}
+
+fn lerp(from: f64, to: f64, amount: f64) -> f64 {
+    from + (to - from) * amount
+}
+
+impl Shape {
+    /// The value at `phase`, in periods, `cycle` is the number of whole periods.
+    pub fn value(self, cycle: usize, phase: f64, seed: usize) -> f64 {
+        use std::f64::consts::PI;
+        match self {
+            Self::Sine => (2.0 * PI * phase).sin(),
+            Self::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Self::Saw => 2.0 * phase - 1.0,
+            Self::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Self::SampleHold => {
+                let bits = random::mix(seed ^ random::mix(cycle) as usize);
+                2.0 * random::unit(bits) - 1.0
+            }
+        }
+    }
+}
+
+impl Lfo {
+    pub fn new(
+        shape: Shape,
+        period_ticks: Arc<BindingSwapGet<usize>>,
+        phase: Arc<BindingSwapGet<Float>>,
+        depth: Arc<BindingSwapGet<Float>>,
+        offset: Arc<BindingSwapGet<Float>>,
+        seed: Arc<BindingSwapGet<usize>>,
+    ) -> Self {
+        Self {
+            shape,
+            period_ticks,
+            phase,
+            depth,
+            offset,
+            seed,
+            tick: AtomicUsize::new(0),
+        }
+    }
+
+    /// The value at `tick`.
+    pub fn value_at(&self, tick: usize) -> Float {
+        let period = self.period_ticks.get();
+        let offset = f64::from(self.offset.get());
+        if period == 0 {
+            return Cast::from_f64(offset);
+        }
+        let pos = (tick % period) as f64 / period as f64 + f64::from(self.phase.get());
+        let cycle = (tick / period).wrapping_add(pos.floor() as isize as usize);
+        let v = self.shape.value(cycle, pos - pos.floor(), self.seed.get());
+        Cast::from_f64(offset + f64::from(self.depth.get()) * v)
+    }
+}
+
+impl Envelope {
+    pub fn new(
+        trigger: Arc<BindingSwapGet<bool>>,
+        attack_ticks: Arc<BindingSwapGet<usize>>,
+        decay_ticks: Arc<BindingSwapGet<usize>>,
+        sustain: Arc<BindingSwapGet<Float>>,
+        release_ticks: Arc<BindingSwapGet<usize>>,
+        depth: Arc<BindingSwapGet<Float>>,
+        offset: Arc<BindingSwapGet<Float>>,
+    ) -> Self {
+        Self {
+            trigger,
+            attack_ticks,
+            decay_ticks,
+            sustain,
+            release_ticks,
+            depth,
+            offset,
+            state: Default::default(),
+        }
+    }
+
+    /// The value at `tick`.
+    pub fn value_at(&self, tick: usize) -> Float {
+        let mut state = self.state.lock();
+        let gate = self.trigger.get();
+        if gate != state.gate {
+            state.gate = gate;
+            state.start = tick;
+            state.from = state.level;
+        }
+        let elapsed = tick.saturating_sub(state.start);
+        let (attack, decay, release) = (
+            self.attack_ticks.get(),
+            self.decay_ticks.get(),
+            self.release_ticks.get(),
+        );
+        let sustain = f64::from(self.sustain.get());
+        state.level = if gate {
+            if elapsed < attack {
+                lerp(state.from, 1.0, elapsed as f64 / attack as f64)
+            } else if elapsed - attack < decay {
+                lerp(1.0, sustain, (elapsed - attack) as f64 / decay as f64)
+            } else {
+                sustain
+            }
+        } else if elapsed < release {
+            lerp(state.from, 0.0, elapsed as f64 / release as f64)
+        } else {
+            0.0
+        };
+        self.output(state.level)
+    }
+
+    /// The value at the last tick it was evaluated at, without evaluating it.
+    pub fn value(&self) -> Float {
+        self.output(self.state.lock().level)
+    }
+
+    fn output(&self, level: f64) -> Float {
+        Cast::from_f64(f64::from(self.offset.get()) + f64::from(self.depth.get()) * level)
+    }
+}
+
+impl ParamBindingGet<Float> for Lfo {
+    fn get(&self) -> Float {
+        let tick = if let Some(tick) = eval_tick() {
+            self.tick.store(tick, Ordering::Relaxed);
+            tick
+        } else {
+            self.tick.load(Ordering::Relaxed)
+        };
+        self.value_at(tick)
+    }
+}
+
+impl ParamBindingGet<Float> for Envelope {
+    fn get(&self) -> Float {
+        if let Some(tick) = eval_tick() {
+            self.value_at(tick)
+        } else {
+            self.value()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot<T>(v: T) -> Arc<BindingSwapGet<T>> {
+        Arc::new(BindingSwapGet::new(v))
+    }
+
+    #[test]
+    fn lfo() {
+        let lfo = Lfo::new(
+            Shape::Saw,
+            slot(4),
+            slot(0.0),
+            slot(2.0),
+            slot(1.0),
+            slot(0),
+        );
+        assert_eq!(-1.0, lfo.value_at(0));
+        assert_eq!(1.0, lfo.value_at(2));
+        assert_eq!(-1.0, lfo.value_at(8));
+
+        let lfo = Lfo::new(
+            Shape::Square,
+            slot(4),
+            slot(0.5),
+            slot(1.0),
+            slot(0.0),
+            slot(0),
+        );
+        assert_eq!(-1.0, lfo.value_at(0));
+        assert_eq!(1.0, lfo.value_at(2));
+
+        let lfo = Lfo::new(
+            Shape::SampleHold,
+            slot(4),
+            slot(0.0),
+            slot(1.0),
+            slot(0.0),
+            slot(3),
+        );
+        assert_eq!(lfo.value_at(4), lfo.value_at(7));
+        assert!(lfo.value_at(4) >= -1.0 && lfo.value_at(4) <= 1.0);
+
+        let lfo = Lfo::new(
+            Shape::Sine,
+            slot(0),
+            slot(0.0),
+            slot(1.0),
+            slot(0.5),
+            slot(0),
+        );
+        assert_eq!(0.5, lfo.value_at(10));
+    }
+
+    #[test]
+    fn envelope() {
+        let trigger = slot(false);
+        let env = Envelope::new(
+            trigger.clone(),
+            slot(4),
+            slot(4),
+            slot(0.5),
+            slot(2),
+            slot(1.0),
+            slot(0.0),
+        );
+        assert_eq!(0.0, env.value_at(0));
+        trigger.bind(Arc::new(true) as Arc<dyn ParamBindingGet<bool>>);
+        assert_eq!(0.0, env.value_at(10));
+        assert_eq!(0.5, env.value_at(12));
+        assert_eq!(1.0, env.value_at(14));
+        assert_eq!(0.75, env.value_at(16));
+        assert_eq!(0.5, env.value_at(100));
+        trigger.unbind();
+        assert_eq!(0.5, env.value_at(200));
+        assert_eq!(0.25, env.value_at(201));
+        assert_eq!(0.0, env.value_at(202));
+    }
+
+    #[test]
+    fn eval() {
+        let trigger = slot(true);
+        let env = Envelope::new(
+            trigger.clone(),
+            slot(4),
+            slot(0),
+            slot(1.0),
+            slot(0),
+            slot(1.0),
+            slot(0.0),
+        );
+        assert_eq!(None, eval_tick());
+        assert_eq!(0.0, env.get());
+        assert_eq!(0.0, with_eval_tick(2, || env.get()));
+        assert_eq!(0.5, with_eval_tick(4, || env.get()));
+        //reads outside of evaluation don't see the trigger change
+        trigger.unbind();
+        assert_eq!(0.5, env.get());
+        assert_eq!(
+            Some(5),
+            with_eval_tick(5, || with_eval_tick(6, eval_tick).and(eval_tick()))
+        );
+        assert_eq!(None, eval_tick());
+
+        let lfo = Lfo::new(
+            Shape::Saw,
+            slot(4),
+            slot(0.0),
+            slot(2.0),
+            slot(1.0),
+            slot(0),
+        );
+        assert_eq!(-1.0, lfo.get());
+        assert_eq!(1.0, with_eval_tick(2, || lfo.get()));
+        assert_eq!(1.0, lfo.get());
+    }
+}
@@ -0,0 +1,424 @@
+//! Operator params: values computed from the values of their param slots.
+
+use crate::error::CreateError;
+use sched::binding::{swap::BindingSwapGet, ParamBindingGet};
+use serde::de::DeserializeOwned;
+use serde_json::value::Value as JsonValue;
+use std::sync::Arc;
+
+/// Arithmetic that never panics.
+///
+/// Integer operations saturate, division and remainder by zero are zero for every type.
+pub trait Arith: Copy {
+    fn add(self, other: Self) -> Self;
+    fn sub(self, other: Self) -> Self;
+    fn mul(self, other: Self) -> Self;
+    fn div(self, other: Self) -> Self;
+    fn rem(self, other: Self) -> Self;
+    fn neg(self) -> Self;
+    fn abs(self) -> Self;
+    /// The largest value of the type.
+    fn max_value() -> Self;
+}
+
+/// Conversion between numeric types, through `i128` for integers and `f64` for floats.
+///
+/// Conversions clamp to the range of the target type, floats round to the nearest integer, half
+/// away from zero, and NaN converts to zero. Bools are `1` for `true`, and any non zero value is
+/// `true`.
+pub trait Cast: Copy {
+    /// Is the type a floating point type.
+    const FLOAT: bool;
+    fn to_i128(self) -> i128;
+    fn to_f64(self) -> f64;
+    fn from_i128(v: i128) -> Self;
+    fn from_f64(v: f64) -> Self;
+}
+
+/// Apply `func` to the value of `input`.
+pub struct UnaryOp<I, O> {
+    func: fn(I) -> O,
+    input: Arc<dyn ParamBindingGet<I>>,
+}
+
+/// Apply `func` to the values of `left` and `right`.
+pub struct BinaryOp<L, R, O> {
+    func: fn(L, R) -> O,
+    left: Arc<dyn ParamBindingGet<L>>,
+    right: Arc<dyn ParamBindingGet<R>>,
+}
+
+/// Apply `func` to the values of `a`, `b` and `c`.
+pub struct TernaryOp<A, B, C, O> {
+    func: fn(A, B, C) -> O,
+    a: Arc<dyn ParamBindingGet<A>>,
+    b: Arc<dyn ParamBindingGet<B>>,
+    c: Arc<dyn ParamBindingGet<C>>,
+}
+
+/// Creation arguments for an operator: initial values for its slots, used until they are bound.
+pub struct SlotArgs<'a> {
+    args: Option<&'a serde_json::Map<String, JsonValue>>,
+}
+
+macro_rules! impl_arith_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl Arith for $t {
+                fn add(self, other: Self) -> Self {
+                    self.saturating_add(other)
+                }
+                fn sub(self, other: Self) -> Self {
+                    self.saturating_sub(other)
+                }
+                fn mul(self, other: Self) -> Self {
+                    self.saturating_mul(other)
+                }
+                fn div(self, other: Self) -> Self {
+                    self.checked_div(other).unwrap_or(0)
+                }
+                fn rem(self, other: Self) -> Self {
+                    self.checked_rem(other).unwrap_or(0)
+                }
+                fn neg(self) -> Self {
+                    0
+                }
+                fn abs(self) -> Self {
+                    self
+                }
+                fn max_value() -> Self {
+                    <$t>::max_value()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_arith_signed {
+    ($($t:ty),*) => {
+        $(
+            impl Arith for $t {
+                fn add(self, other: Self) -> Self {
+                    self.saturating_add(other)
+                }
+                fn sub(self, other: Self) -> Self {
+                    self.saturating_sub(other)
+                }
+                fn mul(self, other: Self) -> Self {
+                    self.saturating_mul(other)
+                }
+                fn div(self, other: Self) -> Self {
+                    if other == 0 {
+                        0
+                    } else {
+                        //only overflows for MIN / -1
+                        self.checked_div(other).unwrap_or(<$t>::max_value())
+                    }
+                }
+                fn rem(self, other: Self) -> Self {
+                    self.checked_rem(other).unwrap_or(0)
+                }
+                fn neg(self) -> Self {
+                    self.saturating_neg()
+                }
+                fn abs(self) -> Self {
+                    self.saturating_abs()
+                }
+                fn max_value() -> Self {
+                    <$t>::max_value()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_arith_float {
+    ($($t:ty),*) => {
+        $(
+            impl Arith for $t {
+                fn add(self, other: Self) -> Self {
+                    self + other
+                }
+                fn sub(self, other: Self) -> Self {
+                    self - other
+                }
+                fn mul(self, other: Self) -> Self {
+                    self * other
+                }
+                fn div(self, other: Self) -> Self {
+                    if other == 0.0 {
+                        0.0
+                    } else {
+                        self / other
+                    }
+                }
+                fn rem(self, other: Self) -> Self {
+                    if other == 0.0 {
+                        0.0
+                    } else {
+                        self % other
+                    }
+                }
+                fn neg(self) -> Self {
+                    -self
+                }
+                fn abs(self) -> Self {
+                    <$t>::abs(self)
+                }
+                fn max_value() -> Self {
+                    <$t>::MAX
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_cast_int {
+    ($($t:ty),*) => {
+        $(
+            impl Cast for $t {
+                const FLOAT: bool = false;
+                fn to_i128(self) -> i128 {
+                    self as i128
+                }
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+                fn from_i128(v: i128) -> Self {
+                    clamp(v, <$t>::min_value() as i128, <$t>::max_value() as i128) as $t
+                }
+                //float to int casts saturate, NaN is zero
+                fn from_f64(v: f64) -> Self {
+                    v.round() as $t
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_cast_float {
+    ($($t:ty),*) => {
+        $(
+            impl Cast for $t {
+                const FLOAT: bool = true;
+                fn to_i128(self) -> i128 {
+                    self.round() as i128
+                }
+                fn to_f64(self) -> f64 {
+                    f64::from(self)
+                }
+                fn from_i128(v: i128) -> Self {
+                    v as $t
+                }
+                fn from_f64(v: f64) -> Self {
+                    v as $t
+                }
+            }
+        )*
+    };
+}
+
+impl Cast for bool {
+    const FLOAT: bool = false;
+    fn to_i128(self) -> i128 {
+        self as i128
+    }
+    fn to_f64(self) -> f64 {
+        if self {
+            1.0
+        } else {
+            0.0
+        }
+    }
+    fn from_i128(v: i128) -> Self {
+        v != 0
+    }
+    fn from_f64(v: f64) -> Self {
+        v != 0.0 && !v.is_nan()
+    }
+}
+
+impl_arith_unsigned!(u8, usize);
+impl_arith_signed!(isize);
+impl_arith_float!(f32, f64);
+impl_cast_int!(u8, usize, isize);
+impl_cast_float!(f32, f64);
+
+/// Convert `v` from `F` to `T`, see `Cast` for the clamping and rounding rules.
+pub fn cast<F: Cast, T: Cast>(v: F) -> T {
+    if F::FLOAT {
+        T::from_f64(v.to_f64())
+    } else {
+        T::from_i128(v.to_i128())
+    }
+}
+
+/// The lesser of `left` and `right`, `left` if they are not comparable.
+pub fn min<T: PartialOrd>(left: T, right: T) -> T {
+    if right < left {
+        right
+    } else {
+        left
+    }
+}
+
+/// The greater of `left` and `right`, `left` if they are not comparable.
+pub fn max<T: PartialOrd>(left: T, right: T) -> T {
+    if right > left {
+        right
+    } else {
+        left
+    }
+}
+
+/// Choose `on_true` if `cond` is true, otherwise `on_false`.
+pub fn select<T>(cond: bool, on_true: T, on_false: T) -> T {
+    if cond {
+        on_true
+    } else {
+        on_false
+    }
+}
+
+/// Limit `value` to `min..=max`, `min` wins if `min > max`.
+pub fn clamp<T: PartialOrd>(value: T, min: T, max: T) -> T {
+    if value < min {
+        min
+    } else if value > max {
+        max
+    } else {
+        value
+    }
+}
+
+impl<I, O> UnaryOp<I, O> {
+    pub fn new(func: fn(I) -> O, input: Arc<dyn ParamBindingGet<I>>) -> Self {
+        Self { func, input }
+    }
+}
+
+impl<L, R, O> BinaryOp<L, R, O> {
+    pub fn new(
+        func: fn(L, R) -> O,
+        left: Arc<dyn ParamBindingGet<L>>,
+        right: Arc<dyn ParamBindingGet<R>>,
+    ) -> Self {
+        Self { func, left, right }
+    }
+}
+
+impl<A, B, C, O> TernaryOp<A, B, C, O> {
+    pub fn new(
+        func: fn(A, B, C) -> O,
+        a: Arc<dyn ParamBindingGet<A>>,
+        b: Arc<dyn ParamBindingGet<B>>,
+        c: Arc<dyn ParamBindingGet<C>>,
+    ) -> Self {
+        Self { func, a, b, c }
+    }
+}
+
+impl<I, O> ParamBindingGet<O> for UnaryOp<I, O>
+where
+    I: Send + Sync,
+    O: Send + Sync,
+{
+    fn get(&self) -> O {
+        (self.func)(self.input.get())
+    }
+}
+
+impl<L, R, O> ParamBindingGet<O> for BinaryOp<L, R, O>
+where
+    L: Send + Sync,
+    R: Send + Sync,
+    O: Send + Sync,
+{
+    fn get(&self) -> O {
+        (self.func)(self.left.get(), self.right.get())
+    }
+}
+
+impl<A, B, C, O> ParamBindingGet<O> for TernaryOp<A, B, C, O>
+where
+    A: Send + Sync,
+    B: Send + Sync,
+    C: Send + Sync,
+    O: Send + Sync,
+{
+    fn get(&self) -> O {
+        (self.func)(self.a.get(), self.b.get(), self.c.get())
+    }
+}
+
+impl<'a> SlotArgs<'a> {
+    /// Validate that `args` is null or an object whose keys are all in `slots`.
+    pub fn new(args: &'a JsonValue, slots: &[&str]) -> Result<Self, CreateError> {
+        match args {
+            JsonValue::Null => Ok(Self { args: None }),
+            JsonValue::Object(map) => {
+                if let Some(key) = map.keys().find(|k| !slots.contains(&k.as_str())) {
+                    Err(CreateError::InvalidArgs(format!("unknown slot {}", key)))
+                } else {
+                    Ok(Self { args: Some(map) })
+                }
+            }
+            v => Err(CreateError::InvalidArgs(format!(
+                "expected an object, got {}",
+                v
+            ))),
+        }
+    }
+
+    /// Create the binding for the slot `name`, with the value from the args or `default`.
+    pub fn slot<T>(&self, name: &str, default: T) -> Result<Arc<BindingSwapGet<T>>, CreateError>
+    where
+        T: DeserializeOwned,
+    {
+        let value = if let Some(v) = self.args.and_then(|a| a.get(name)) {
+            serde_json::from_value(v.clone()).map_err(|e| {
+                CreateError::InvalidArgs(format!("invalid value {} for slot {}: {}", v, name, e))
+            })?
+        } else {
+            default
+        };
+        Ok(Arc::new(BindingSwapGet::new(value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arith() {
+        assert_eq!(255u8, Arith::add(250u8, 10));
+        assert_eq!(0u8, Arith::sub(5u8, 10));
+        assert_eq!(0usize, Arith::div(5usize, 0));
+        assert_eq!(0usize, Arith::rem(5usize, 0));
+        assert_eq!(isize::max_value(), Arith::neg(isize::min_value()));
+        assert_eq!(isize::max_value(), Arith::abs(isize::min_value()));
+        assert_eq!(isize::max_value(), Arith::div(isize::min_value(), -1));
+        assert_eq!(0.0f64, Arith::div(1.0f64, 0.0));
+        assert_eq!(2.5f64, clamp(4.0, 0.0, 2.5));
+        assert_eq!(3, min(3, 4));
+        assert_eq!(4, max(3, 4));
+        assert_eq!(2, select(false, 1, 2));
+    }
+
+    #[test]
+    fn casts() {
+        assert_eq!(255u8, cast(300.0f64));
+        assert_eq!(0u8, cast(-3.0f64));
+        assert_eq!(64u8, cast(63.5f64));
+        assert_eq!(0u8, cast(std::f64::NAN));
+        assert_eq!(255u8, cast(1000usize));
+        assert_eq!(0usize, cast(-5isize));
+        assert_eq!(-4isize, cast(-3.5f64));
+        assert_eq!(1u8, cast(true));
+        assert!(cast::<f64, bool>(0.1));
+        assert!(!cast::<u8, bool>(0));
+        assert_eq!(isize::max_value(), cast(usize::max_value()));
+        assert_eq!(2.0f64, cast(2u8));
+    }
+}
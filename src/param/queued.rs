@@ -0,0 +1,196 @@
+//! Shadow access that queues sets for the scheduler thread.
+//!
+//! Control threads set values through a `QueuedValue`, the value is pushed onto its scheduler's
+//! bounded lock-free `SetQueue` and the scheduler thread applies every queued set at the start of
+//! its next block, so values that are set together take effect together. Sets are never applied
+//! from the control thread, if the queue is full the set is dropped and reported. Getting a
+//! `QueuedValue` reads the value the scheduler is using.
+//!
+//! Anything the scheduler thread would free, including the applied sets, is pushed onto another
+//! preallocated queue with `dispose` and dropped by the dispose thread with `dispose_all`. Values
+//! the scheduler thread may still be reading are handed over with `retire` and only become
+//! disposable at the start of the next block.
+
+use crossbeam_queue::ArrayQueue;
+use sched::binding::{ParamBinding, ParamBindingGet, ParamBindingSet};
+use std::sync::Arc;
+
+/// The maximum number of sets waiting for the scheduler, and of items waiting for the dispose
+/// thread.
+pub const QUEUE_LEN: usize = 1024;
+
+/// A scheduler's queue of sets, and of items to drop in its dispose thread.
+pub struct SetQueue {
+    sets: ArrayQueue<Box<dyn ApplySet>>,
+    retired: ArrayQueue<Disposable>,
+    dispose: ArrayQueue<Disposable>,
+}
+
+/// Something for the dispose thread to drop, converting into it doesn't allocate.
+pub enum Disposable {
+    Box(Box<dyn Send>),
+    Arc(Arc<dyn Send + Sync>),
+}
+
+/// A binding whose sets are queued and applied to `binding` in the scheduler thread.
+pub struct QueuedValue<T> {
+    binding: Arc<dyn ParamBinding<T>>,
+    queue: Arc<SetQueue>,
+}
+
+//a value waiting to be set
+struct Set<T> {
+    binding: Arc<dyn ParamBinding<T>>,
+    value: Option<T>,
+}
+
+trait ApplySet: Send {
+    fn apply(&mut self);
+    fn into_dispose(self: Box<Self>) -> Box<dyn Send>;
+}
+
+impl SetQueue {
+    pub fn new() -> Self {
+        Self {
+            sets: ArrayQueue::new(QUEUE_LEN),
+            retired: ArrayQueue::new(QUEUE_LEN),
+            dispose: ArrayQueue::new(QUEUE_LEN),
+        }
+    }
+
+    /// Apply all the queued sets, in the order they were queued, called by the scheduler thread
+    /// at the start of each block.
+    ///
+    /// The sets, and the bindings of params that have been removed since, are dropped in the
+    /// dispose thread, along with everything retired before this block.
+    pub fn apply(&self) {
+        while let Some(item) = self.retired.pop() {
+            self.dispose(item);
+        }
+        while let Some(mut set) = self.sets.pop() {
+            set.apply();
+            self.dispose(set.into_dispose());
+        }
+    }
+
+    /// Have `item` dropped in the dispose thread, doesn't allocate so it can be called from the
+    /// scheduler thread.
+    ///
+    /// If the dispose thread has fallen so far behind that the queue is full the item is leaked
+    /// rather than freed in the calling thread.
+    pub fn dispose<D: Into<Disposable>>(&self, item: D) {
+        if let Err(item) = self.dispose.push(item.into()) {
+            std::mem::forget(item);
+        }
+    }
+
+    /// Have `item` dropped in the dispose thread once the scheduler thread starts its next block,
+    /// for values that it may still hold references to in the current block.
+    ///
+    /// Like `dispose` it doesn't allocate and leaks the item if the queue is full.
+    pub fn retire<D: Into<Disposable>>(&self, item: D) {
+        if let Err(item) = self.retired.push(item.into()) {
+            std::mem::forget(item);
+        }
+    }
+
+    /// Drop everything that has been handed to `dispose`, called by the dispose thread.
+    pub fn dispose_all(&self) {
+        while self.dispose.pop().is_some() {}
+    }
+}
+
+impl Default for SetQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Box<dyn Send>> for Disposable {
+    fn from(item: Box<dyn Send>) -> Self {
+        Self::Box(item)
+    }
+}
+
+impl<T> From<Arc<T>> for Disposable
+where
+    T: Send + Sync + 'static,
+{
+    fn from(item: Arc<T>) -> Self {
+        Self::Arc(item)
+    }
+}
+
+impl<T> QueuedValue<T> {
+    pub fn new(binding: Arc<dyn ParamBinding<T>>, queue: Arc<SetQueue>) -> Self {
+        Self { binding, queue }
+    }
+}
+
+impl<T> ApplySet for Set<T>
+where
+    T: Send + 'static,
+{
+    fn apply(&mut self) {
+        if let Some(v) = self.value.take() {
+            self.binding.set(v);
+        }
+    }
+
+    fn into_dispose(self: Box<Self>) -> Box<dyn Send> {
+        self
+    }
+}
+
+impl<T> ParamBindingGet<T> for QueuedValue<T>
+where
+    T: Send + Sync,
+{
+    fn get(&self) -> T {
+        self.binding.get()
+    }
+}
+
+impl<T> ParamBindingSet<T> for QueuedValue<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn set(&self, value: T) {
+        let set = Box::new(Set {
+            binding: self.binding.clone(),
+            value: Some(value),
+        });
+        if self.queue.sets.push(set).is_err() {
+            eprintln!("set queue full, dropping the set");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sched::atomic::Atomic;
+
+    #[test]
+    fn queued() {
+        let queue = Arc::new(SetQueue::new());
+        let value = Arc::new(Atomic::new(1usize));
+        let shadow = QueuedValue::new(value.clone() as Arc<dyn ParamBinding<usize>>, queue.clone());
+        shadow.set(2);
+        shadow.set(3);
+        assert_eq!(1, shadow.get());
+        queue.apply();
+        assert_eq!(3, value.get());
+        assert_eq!(3, shadow.get());
+        queue.dispose_all();
+
+        //a full queue drops sets rather than applying them
+        for v in 0..QUEUE_LEN {
+            shadow.set(v);
+        }
+        shadow.set(QUEUE_LEN);
+        assert_eq!(3, shadow.get());
+        queue.apply();
+        assert_eq!(QUEUE_LEN - 1, shadow.get());
+    }
+}
@@ -0,0 +1,258 @@
+//! Random values, from a seeded generator so that sequences can be reproduced.
+//!
+//! Like the other modulation sources a new value is drawn for each tick the scheduler evaluates,
+//! reads from other threads see the value at the last evaluated tick.
+
+use crate::param::{array::BindingSwapKeyValueGet, modulation::eval_tick, ops::Cast};
+use sched::{
+    binding::{swap::BindingSwapGet, ParamBindingGet, ParamBindingKeyValueGet},
+    Float,
+};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Random bits for each tick, from the tick and the value of its seed binding.
+pub struct Rng {
+    seed: Arc<BindingSwapGet<usize>>,
+    //the last tick it was evaluated at
+    tick: AtomicUsize,
+}
+
+/// A uniform random value in `min..=max` for integers and `min..max` for floats, `min` if the
+/// range is empty.
+pub struct RandomGet<T> {
+    rng: Rng,
+    min: Arc<BindingSwapGet<T>>,
+    max: Arc<BindingSwapGet<T>>,
+}
+
+/// A random value from `values` for each tick, chosen with probability proportional to its
+/// weight.
+///
+/// Missing and negative weights are zero, if there are no positive weights the choice is uniform.
+pub struct ChoiceGet<T> {
+    rng: Rng,
+    values: Arc<BindingSwapKeyValueGet<T>>,
+    weights: Arc<BindingSwapKeyValueGet<Float>>,
+}
+
+/// Scale random bits to a value in a range.
+pub trait Uniform: Copy {
+    /// A value in `min..=max` for integers and `min..max` for floats, from the random `bits`,
+    /// `min` if `max < min`.
+    fn uniform(bits: u64, min: Self, max: Self) -> Self;
+}
+
+/// A seed from the current time, for params created without one.
+pub fn time_seed() -> usize {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as usize)
+}
+
+/// Scramble `seed` with splitmix64, every seed, including zero, gives a good non zero state.
+pub fn mix(seed: usize) -> u64 {
+    let mut z = (seed as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// A float in `0.0..1.0` from the top 53 of the random `bits`.
+pub fn unit(bits: u64) -> f64 {
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl Rng {
+    pub fn new(seed: Arc<BindingSwapGet<usize>>) -> Self {
+        Self {
+            seed,
+            tick: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the random bits for the tick being evaluated, or the last tick evaluated when read
+    /// outside of evaluation.
+    pub fn bits(&self) -> u64 {
+        let tick = if let Some(tick) = eval_tick() {
+            self.tick.store(tick, Ordering::Relaxed);
+            tick
+        } else {
+            self.tick.load(Ordering::Relaxed)
+        };
+        mix(self.seed.get() ^ mix(tick) as usize)
+    }
+}
+
+impl<T> RandomGet<T> {
+    pub fn new(
+        seed: Arc<BindingSwapGet<usize>>,
+        min: Arc<BindingSwapGet<T>>,
+        max: Arc<BindingSwapGet<T>>,
+    ) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            min,
+            max,
+        }
+    }
+}
+
+impl<T> ChoiceGet<T> {
+    pub fn new(
+        seed: Arc<BindingSwapGet<usize>>,
+        values: Arc<BindingSwapKeyValueGet<T>>,
+        weights: Arc<BindingSwapKeyValueGet<Float>>,
+    ) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            values,
+            weights,
+        }
+    }
+}
+
+macro_rules! impl_uniform_int {
+    ($($t:ty),*) => {
+        $(
+            impl Uniform for $t {
+                fn uniform(bits: u64, min: Self, max: Self) -> Self {
+                    let (lo, hi) = (min.to_i128(), max.to_i128());
+                    if hi <= lo {
+                        min
+                    } else {
+                        Self::from_i128(lo + (bits as i128) % (hi - lo + 1))
+                    }
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_uniform_float {
+    ($($t:ty),*) => {
+        $(
+            impl Uniform for $t {
+                fn uniform(bits: u64, min: Self, max: Self) -> Self {
+                    if max < min {
+                        min
+                    } else {
+                        min + (max - min) * Self::from_f64(unit(bits))
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_uniform_int!(u8, usize, isize);
+impl_uniform_float!(f32, f64);
+
+impl<T> ParamBindingGet<T> for RandomGet<T>
+where
+    T: Uniform + Send + Sync,
+{
+    fn get(&self) -> T {
+        T::uniform(self.rng.bits(), self.min.get(), self.max.get())
+    }
+}
+
+impl<T> ParamBindingGet<T> for ChoiceGet<T>
+where
+    T: Copy + Default + Send + Sync,
+{
+    fn get(&self) -> T {
+        let len = self.values.len().unwrap_or(0);
+        if len == 0 {
+            return Default::default();
+        }
+        let weight = |i: usize| -> f64 {
+            let w = self.weights.get_at(i).map_or(0.0, f64::from);
+            if w > 0.0 {
+                w
+            } else {
+                0.0
+            }
+        };
+        let total: f64 = (0..len).map(weight).sum();
+        let bits = self.rng.bits();
+        let index = if total > 0.0 {
+            let mut r = unit(bits) * total;
+            (0..len)
+                .find(|i| {
+                    r -= weight(*i);
+                    r < 0.0
+                })
+                //rounding can leave a little over, it belongs to the last weighted value
+                .unwrap_or_else(|| (0..len).rev().find(|i| weight(*i) > 0.0).unwrap_or(0))
+        } else {
+            (bits % len as u64) as usize
+        };
+        self.values.get_at(index).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::param::{array::ArrayConst, modulation::with_eval_tick};
+
+    #[test]
+    fn random() {
+        let seed = Arc::new(BindingSwapGet::new(7usize));
+        let min = Arc::new(BindingSwapGet::new(10u8));
+        let max = Arc::new(BindingSwapGet::new(20u8));
+        let r = RandomGet::new(seed.clone(), min, max);
+        let a: Vec<u8> = (0..32).map(|t| with_eval_tick(t, || r.get())).collect();
+        assert!(a.iter().all(|v| *v >= 10 && *v <= 20));
+        assert!(a.iter().any(|v| *v != a[0]));
+
+        //one value per tick, reads outside of evaluation see the last one
+        assert_eq!(a[31], with_eval_tick(31, || r.get()));
+        assert_eq!(a[31], r.get());
+
+        //reseeding restarts the sequence
+        seed.bind(Arc::new(8usize) as Arc<dyn ParamBindingGet<usize>>);
+        let b: Vec<u8> = (0..32).map(|t| with_eval_tick(t, || r.get())).collect();
+        assert_ne!(a, b);
+        seed.unbind();
+        let c: Vec<u8> = (0..32).map(|t| with_eval_tick(t, || r.get())).collect();
+        assert_eq!(a, c);
+
+        let r = RandomGet::new(
+            Arc::new(BindingSwapGet::new(0usize)),
+            Arc::new(BindingSwapGet::new(-1.0f64)),
+            Arc::new(BindingSwapGet::new(1.0f64)),
+        );
+        assert!((0..32)
+            .map(|t| with_eval_tick(t, || r.get()))
+            .all(|v| v >= -1.0 && v < 1.0));
+    }
+
+    #[test]
+    fn choice() {
+        let values: Arc<BindingSwapKeyValueGet<u8>> = Default::default();
+        let weights: Arc<BindingSwapKeyValueGet<Float>> = Default::default();
+        let c = ChoiceGet::new(
+            Arc::new(BindingSwapGet::new(1usize)),
+            values.clone(),
+            weights.clone(),
+        );
+        assert_eq!(0, c.get());
+
+        values.bind(
+            Arc::new(ArrayConst::new(vec![1u8, 2, 3])) as Arc<dyn ParamBindingKeyValueGet<u8>>
+        );
+        assert!((0..32)
+            .map(|t| with_eval_tick(t, || c.get()))
+            .all(|v| v >= 1 && v <= 3));
+
+        let w: Vec<Float> = vec![0.0, -1.0, 2.0];
+        weights.bind(Arc::new(ArrayConst::new(w)) as Arc<dyn ParamBindingKeyValueGet<Float>>);
+        assert!((0..32)
+            .map(|t| with_eval_tick(t, || c.get()))
+            .all(|v| v == 3));
+    }
+}
@@ -0,0 +1,202 @@
+//! Musical scales, for quantizing values to MIDI note numbers.
+
+use crate::error::CreateError;
+use sched::binding::{swap::BindingSwapGet, ParamBindingGet};
+use serde_json::value::Value as JsonValue;
+use std::sync::Arc;
+
+/// Built in scales and modes, semitones from the root.
+pub const SCALES: &[(&str, &[u8])] = &[
+    ("major", &[0, 2, 4, 5, 7, 9, 11]),
+    ("ionian", &[0, 2, 4, 5, 7, 9, 11]),
+    ("dorian", &[0, 2, 3, 5, 7, 9, 10]),
+    ("phrygian", &[0, 1, 3, 5, 7, 8, 10]),
+    ("lydian", &[0, 2, 4, 6, 7, 9, 11]),
+    ("mixolydian", &[0, 2, 4, 5, 7, 9, 10]),
+    ("minor", &[0, 2, 3, 5, 7, 8, 10]),
+    ("aeolian", &[0, 2, 3, 5, 7, 8, 10]),
+    ("locrian", &[0, 1, 3, 5, 6, 8, 10]),
+    ("harmonic_minor", &[0, 2, 3, 5, 7, 8, 11]),
+    ("melodic_minor", &[0, 2, 3, 5, 7, 9, 11]),
+    ("major_pentatonic", &[0, 2, 4, 7, 9]),
+    ("minor_pentatonic", &[0, 3, 5, 7, 10]),
+    ("blues", &[0, 3, 5, 6, 7, 10]),
+    ("whole_tone", &[0, 2, 4, 6, 8, 10]),
+    ("chromatic", &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]),
+];
+
+/// A scale, ascending semitones from the root, all within an octave.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Scale(Vec<u8>);
+
+/// The note for `degree` of `scale`, starting at `root`, transposed by `octave` octaves.
+///
+/// Negative degrees are below the root, notes are clamped to 0..=127.
+pub struct ScaleQuantize {
+    scale: Scale,
+    degree: Arc<BindingSwapGet<isize>>,
+    root: Arc<BindingSwapGet<u8>>,
+    octave: Arc<BindingSwapGet<isize>>,
+}
+
+/// The tone of `scale`, starting at `root`, nearest to the note `input`, the lower on a tie.
+pub struct ScaleSnap {
+    scale: Scale,
+    input: Arc<BindingSwapGet<u8>>,
+    root: Arc<BindingSwapGet<u8>>,
+}
+
+fn clamp_note(note: isize) -> u8 {
+    crate::param::ops::clamp(note, 0, 127) as u8
+}
+
+impl Scale {
+    /// Create a scale from semitones, they must be ascending and less than 12.
+    pub fn new(intervals: Vec<u8>) -> Result<Self, CreateError> {
+        if intervals.is_empty() {
+            Err(CreateError::InvalidArgs("scale is empty".into()))
+        } else if intervals.iter().any(|i| *i >= 12) || intervals.windows(2).any(|w| w[0] >= w[1]) {
+            Err(CreateError::InvalidArgs(format!(
+                "scale intervals {:?} must be ascending and less than 12",
+                intervals
+            )))
+        } else {
+            Ok(Self(intervals))
+        }
+    }
+
+    /// Look up a built in scale by name.
+    pub fn named(name: &str) -> Option<Self> {
+        SCALES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, i)| Self(i.to_vec()))
+    }
+
+    /// Create a scale from a built in scale name or an array of intervals, major if `value` is
+    /// null.
+    pub fn from_json(value: Option<&JsonValue>) -> Result<Self, CreateError> {
+        match value {
+            None | Some(JsonValue::Null) => Ok(Self::named("major").unwrap()),
+            Some(JsonValue::String(name)) => Self::named(name)
+                .ok_or_else(|| CreateError::InvalidArgs(format!("unknown scale {}", name))),
+            Some(v) => {
+                let intervals: Vec<u8> = serde_json::from_value(v.clone())
+                    .map_err(|e| CreateError::InvalidArgs(format!("invalid scale: {}", e)))?;
+                Self::new(intervals)
+            }
+        }
+    }
+
+    /// The note for `degree`, starting at `root`, transposed by `octave` octaves.
+    pub fn note(&self, root: u8, octave: isize, degree: isize) -> u8 {
+        let len = self.0.len() as isize;
+        let octave = octave.saturating_add(degree.div_euclid(len));
+        let interval = self.0[degree.rem_euclid(len) as usize] as isize;
+        clamp_note(
+            (root as isize)
+                .saturating_add(octave.saturating_mul(12))
+                .saturating_add(interval),
+        )
+    }
+
+    /// The scale tone nearest to `note`, starting at `root`, the lower on a tie.
+    pub fn snap(&self, root: u8, note: u8) -> u8 {
+        let note = note as isize;
+        let class = (note - root as isize).rem_euclid(12);
+        let base = note - class;
+        let nearest = self
+            .0
+            .iter()
+            .flat_map(|i| {
+                let i = *i as isize;
+                (-1..=1).map(move |o| i + o * 12)
+            })
+            .min_by_key(|i| ((i - class).abs(), *i))
+            .unwrap_or(class);
+        clamp_note(base + nearest)
+    }
+}
+
+impl ScaleQuantize {
+    pub fn new(
+        scale: Scale,
+        degree: Arc<BindingSwapGet<isize>>,
+        root: Arc<BindingSwapGet<u8>>,
+        octave: Arc<BindingSwapGet<isize>>,
+    ) -> Self {
+        Self {
+            scale,
+            degree,
+            root,
+            octave,
+        }
+    }
+}
+
+impl ScaleSnap {
+    pub fn new(
+        scale: Scale,
+        input: Arc<BindingSwapGet<u8>>,
+        root: Arc<BindingSwapGet<u8>>,
+    ) -> Self {
+        Self { scale, input, root }
+    }
+}
+
+impl ParamBindingGet<u8> for ScaleQuantize {
+    fn get(&self) -> u8 {
+        self.scale
+            .note(self.root.get(), self.octave.get(), self.degree.get())
+    }
+}
+
+impl ParamBindingGet<u8> for ScaleSnap {
+    fn get(&self) -> u8 {
+        self.scale.snap(self.root.get(), self.input.get())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn note() {
+        let major = Scale::named("major").unwrap();
+        assert_eq!(60, major.note(60, 0, 0));
+        assert_eq!(64, major.note(60, 0, 2));
+        assert_eq!(72, major.note(60, 0, 7));
+        assert_eq!(59, major.note(60, 0, -1));
+        assert_eq!(48, major.note(60, -1, 0));
+        assert_eq!(127, major.note(120, 1, 0));
+        assert_eq!(0, major.note(0, 0, -3));
+
+        let custom = Scale::from_json(Some(&json!([0, 7]))).unwrap();
+        assert_eq!(67, custom.note(60, 0, 1));
+        assert_eq!(72, custom.note(60, 0, 2));
+    }
+
+    #[test]
+    fn snap() {
+        let major = Scale::named("major").unwrap();
+        assert_eq!(60, major.snap(60, 60));
+        assert_eq!(60, major.snap(60, 61));
+        assert_eq!(65, major.snap(60, 66));
+        assert_eq!(71, major.snap(60, 71));
+        let pentatonic = Scale::named("minor_pentatonic").unwrap();
+        assert_eq!(70, pentatonic.snap(60, 71));
+        assert_eq!(60, pentatonic.snap(62, 59));
+    }
+
+    #[test]
+    fn from_json() {
+        assert!(Scale::from_json(None).is_ok());
+        assert!(Scale::from_json(Some(&json!("dorian"))).is_ok());
+        assert!(Scale::from_json(Some(&json!("nope"))).is_err());
+        assert!(Scale::from_json(Some(&json!([]))).is_err());
+        assert!(Scale::from_json(Some(&json!([0, 12]))).is_err());
+        assert!(Scale::from_json(Some(&json!([4, 2]))).is_err());
+    }
+}
@@ -0,0 +1,64 @@
+//! String values, which cannot be stored atomically.
+
+use crate::param::queued::SetQueue;
+use sched::{
+    binding::{ParamBindingGet, ParamBindingSet},
+    mutex::Mutex,
+};
+use std::sync::Arc;
+
+/// A shared string value, set in the scheduler thread and read from any thread.
+///
+/// Getting the value clones the `Arc` so it doesn't allocate. The replaced string is retired, so it
+/// is only dropped, in the dispose thread, after the block it was replaced in has released the
+/// clones it read. Readers in the scheduler thread must not keep the string past the block they
+/// read it in.
+pub struct StringValue {
+    value: Mutex<Arc<String>>,
+    queue: Arc<SetQueue>,
+}
+
+impl StringValue {
+    /// A string `value`, replaced strings are disposed with `queue`.
+    pub fn new(value: Arc<String>, queue: Arc<SetQueue>) -> Self {
+        Self {
+            value: Mutex::new(value),
+            queue,
+        }
+    }
+}
+
+impl ParamBindingGet<Arc<String>> for StringValue {
+    fn get(&self) -> Arc<String> {
+        self.value.lock().clone()
+    }
+}
+
+impl ParamBindingSet<Arc<String>> for StringValue {
+    fn set(&self, value: Arc<String>) {
+        let old = std::mem::replace(&mut *self.value.lock(), value);
+        self.queue.retire(old);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retire() {
+        let queue = Arc::new(SetQueue::new());
+        let value = StringValue::new(Arc::new("a".to_string()), queue.clone());
+        let old = Arc::downgrade(&value.get());
+        value.set(Arc::new("b".to_string()));
+        assert_eq!("b", value.get().as_str());
+
+        //not disposable until the next block starts
+        queue.dispose_all();
+        assert!(old.upgrade().is_some());
+        queue.apply();
+        assert!(old.upgrade().is_some());
+        queue.dispose_all();
+        assert!(old.upgrade().is_none());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::param::queued::SetQueue;
 use ::sched::{
     event::EventContainer,
     item_sink::{ItemDispose, ItemSink},
@@ -7,7 +8,8 @@ use ::sched::{
     schedule::ScheduleExecutor,
 };
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    mpsc::SyncSender,
     Arc,
 };
 
@@ -22,17 +24,25 @@ pub type MidiEventSource = ArcMutex<dyn ItemSource<TickedMidiValueEvent>>;
 
 pub type EventQueue = ArcMutex<BinaryHeapQueue<EventContainer>>;
 
+/// Send items here to have them dropped in the dispose thread.
+pub type DisposeSender = SyncSender<Box<dyn Send>>;
+
 pub struct Sched {
     fill_dispose_continue: Arc<AtomicBool>,
     fill_dispose_handle: Option<std::thread::JoinHandle<()>>,
     executor: ScheduleExecutor<SchedDequeue, SchedEnqueue, EventSink>,
     queue_sources: Arc<SchedQueueSources>,
+    tick_next: Arc<AtomicUsize>,
+    set_queue: Arc<SetQueue>,
 }
 
 struct SchedQueueSources {
     midi_queue: MidiValueQueue,
     midi_event_source: MidiEventSource,
     sched_queue: EventQueue,
+    dispose_sender: DisposeSender,
+    tick_next: Arc<AtomicUsize>,
+    set_queue: Arc<SetQueue>,
 }
 
 pub trait IntoPtrs {
@@ -44,6 +54,11 @@ pub trait QueueSource {
     fn midi_queue(&self) -> MidiValueQueue;
     fn midi_event_source(&self) -> MidiEventSource;
     fn sched_queue(&self) -> EventQueue;
+    fn dispose_sender(&self) -> DisposeSender;
+    /// The queue of param sets applied at the start of each `Sched::run`.
+    fn set_queue(&self) -> Arc<SetQueue>;
+    /// The next tick that the scheduler will process, updated after each `Sched::run`.
+    fn tick_next(&self) -> usize;
 }
 
 impl<T> IntoPtrs for T
@@ -67,6 +82,7 @@ impl Sched {
         let sched_queue: ArcMutex<BinaryHeapQueue<EventContainer>> = Default::default();
 
         let (dispose_sink, dispose) = ::sched::std::channel_item_sink::channel_item_sink(1024);
+        let (dispose_sender, dispose_receiver) = std::sync::mpsc::sync_channel(1024);
         let dispose_sink: ArcMutex<dyn ItemSink<EventContainer>> = dispose_sink.into_alock();
 
         let (mut midi_creator, midi_event_source) =
@@ -81,10 +97,17 @@ impl Sched {
         );
 
         let fill_dispose_continue = Arc::new(AtomicBool::new(true));
-
-        let mut fill_dispose = move || {
-            midi_creator.fill().expect("failed to fill midi");
-            dispose.dispose_all().expect("dispose failed");
+        let set_queue = Arc::new(SetQueue::new());
+
+        let mut fill_dispose = {
+            let set_queue = set_queue.clone();
+            move || {
+                midi_creator.fill().expect("failed to fill midi");
+                dispose.dispose_all().expect("dispose failed");
+                //drop items that were handed off from other threads
+                while let Ok(_) = dispose_receiver.try_recv() {}
+                set_queue.dispose_all();
+            }
         };
 
         let fill_dispose_handle = {
@@ -98,10 +121,14 @@ impl Sched {
             })
         };
 
+        let tick_next: Arc<AtomicUsize> = Default::default();
         let queue_sources = Arc::new(SchedQueueSources::new(
             midi_queue,
             midi_event_source,
             sched_queue,
+            dispose_sender,
+            tick_next.clone(),
+            set_queue.clone(),
         ));
 
         Self {
@@ -109,11 +136,16 @@ impl Sched {
             fill_dispose_continue,
             executor: ex,
             queue_sources,
+            tick_next,
+            set_queue,
         }
     }
 
     pub fn run(&mut self, frames: usize, sample_rate: usize) {
+        self.set_queue.apply();
         self.executor.run(frames, sample_rate);
+        self.tick_next
+            .store(self.executor.tick_next(), Ordering::Release);
     }
 
     pub fn tick_next(&self) -> usize {
@@ -130,11 +162,17 @@ impl SchedQueueSources {
         midi_queue: MidiValueQueue,
         midi_event_source: MidiEventSource,
         sched_queue: EventQueue,
+        dispose_sender: DisposeSender,
+        tick_next: Arc<AtomicUsize>,
+        set_queue: Arc<SetQueue>,
     ) -> Self {
         Self {
             midi_queue,
             midi_event_source,
             sched_queue,
+            dispose_sender,
+            tick_next,
+            set_queue,
         }
     }
 }
@@ -151,6 +189,18 @@ impl QueueSource for SchedQueueSources {
     fn sched_queue(&self) -> EventQueue {
         self.sched_queue.clone()
     }
+
+    fn dispose_sender(&self) -> DisposeSender {
+        self.dispose_sender.clone()
+    }
+
+    fn set_queue(&self) -> Arc<SetQueue> {
+        self.set_queue.clone()
+    }
+
+    fn tick_next(&self) -> usize {
+        self.tick_next.load(Ordering::Acquire)
+    }
 }
 
 impl Drop for Sched {